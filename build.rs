@@ -0,0 +1,42 @@
+//! Detects what the compiler expects from the expansion of the macros, which changes between nightlies:
+//! - `fast_async_trait_nightly`: the compiler supports nightly features at all
+//! - `fast_async_trait_define_opaque`: functions defining a free `type Alias = impl Trait` must be annotated with `#[define_opaque(Alias)]` (since nightly-2025-03-12)
+
+use std::{env, process::Command};
+
+/// First nightly to require `#[define_opaque(..)]`
+const DEFINE_OPAQUE: (u32, u32, u32) = (2025, 3, 12);
+
+fn main () {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=RUSTC");
+    println!("cargo:rustc-check-cfg=cfg(fast_async_trait_nightly)");
+    println!("cargo:rustc-check-cfg=cfg(fast_async_trait_define_opaque)");
+
+    let rustc = env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    let version = match Command::new(rustc).arg("--version").output() {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout).into_owned(),
+        _ => return
+    };
+
+    // i.e. `rustc 1.87.0-nightly (00f245915 2025-03-12)`
+    if !version.contains("-nightly") && !version.contains("-dev") {
+        return
+    }
+    println!("cargo:rustc-cfg=fast_async_trait_nightly");
+
+    // builds without a commit date are assumed to be recent
+    let date = version.trim_end()
+        .strip_suffix(')')
+        .and_then(|x| x.rsplit(' ').next())
+        .and_then(parse_date);
+
+    if date.is_none_or(|date| date >= DEFINE_OPAQUE) {
+        println!("cargo:rustc-cfg=fast_async_trait_define_opaque");
+    }
+}
+
+fn parse_date (date: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = date.split('-').map(|x| x.parse::<u32>().ok());
+    return Some((parts.next()??, parts.next()??, parts.next()??))
+}
@@ -0,0 +1,191 @@
+use std::collections::HashSet;
+use proc_macro2::{TokenStream, TokenTree, Span};
+use syn::{*, spanned::Spanned, visit::Visit};
+use fast_async_trait_codegen::{build::Annotated, returns_future};
+
+#[derive(Clone)]
+pub enum Level {
+    Error,
+    Warning,
+}
+
+/// Problem found in an annotated item, either before or after expanding it
+#[derive(Clone)]
+pub struct Diagnostic {
+    pub level: Level,
+    pub span: Span,
+    pub message: String,
+}
+
+impl Diagnostic {
+    #[inline]
+    pub fn error (span: Span, message: impl Into<String>) -> Self {
+        return Self { level: Level::Error, span, message: message.into() }
+    }
+
+    #[inline]
+    pub fn warning (span: Span, message: impl Into<String>) -> Self {
+        return Self { level: Level::Warning, span, message: message.into() }
+    }
+}
+
+/// Flags the constructs the macros accept, but generate code that may not compile for
+pub fn check_item (item: &Annotated) -> Vec<Diagnostic> {
+    let mut result = Vec::new();
+
+    match item {
+        Annotated::Def(_, ItemTrait { items, .. }) => for item in items {
+            if let TraitItem::Method(TraitItemMethod { sig, default, .. }) = item {
+                if is_async(sig) {
+                    check_receiver(sig, &mut result);
+                    check_lifetimes(sig, &mut result);
+                    if default.is_some() {
+                        check_default(sig, &mut result);
+                    }
+                }
+            }
+        },
+
+        Annotated::Impl(_, ItemImpl { items, .. }) => for item in items {
+            if let ImplItem::Method(ImplItemMethod { sig, .. }) = item {
+                if is_async(sig) {
+                    check_receiver(sig, &mut result);
+                    check_lifetimes(sig, &mut result);
+                }
+            }
+        }
+    }
+
+    return result
+}
+
+/// Collects the `compile_error!`s of an expansion, skipping the ones inside the trait's marker
+pub fn compile_errors (tokens: TokenStream) -> Vec<Diagnostic> {
+    let mut result = Vec::new();
+    let mut tokens = tokens.into_iter().peekable();
+
+    while let Some(tt) = tokens.next() {
+        match tt {
+            TokenTree::Ident(ident) if ident == "macro_rules" => {
+                // `!`, the macro's name and its body
+                tokens.next();
+                tokens.next();
+                tokens.next();
+            },
+
+            TokenTree::Ident(ident) if ident == "compile_error" => {
+                if matches!(tokens.peek(), Some(TokenTree::Punct(x)) if x.as_char() == '!') {
+                    tokens.next();
+                    if let Some(TokenTree::Group(group)) = tokens.next() {
+                        let message = parse2::<LitStr>(group.stream()).map_or_else(|_| group.stream().to_string(), |x| x.value());
+                        result.push(Diagnostic::error(ident.span(), message));
+                    }
+                }
+            },
+
+            TokenTree::Group(group) => result.extend(compile_errors(group.stream())),
+            _ => {}
+        }
+    }
+
+    return result
+}
+
+/// Reports the async methods borrowing from more than one lifetime, which the macros don't support yet
+pub fn check_lifetimes (sig: &Signature, result: &mut Vec<Diagnostic>) {
+    let mut lifetimes = Lifetimes::default();
+    for input in &sig.inputs {
+        match input {
+            FnArg::Receiver(Receiver { reference: Some((_, lifetime)), .. }) => match lifetime {
+                Some(lifetime) => lifetimes.visit_lifetime(lifetime),
+                None => lifetimes.elided += 1
+            },
+            FnArg::Receiver(_) => {},
+            FnArg::Typed(PatType { ty, .. }) => lifetimes.visit_type(ty)
+        }
+    }
+
+    if lifetimes.named.len() + lifetimes.elided > 1 {
+        let ident = &sig.ident;
+        result.push(Diagnostic::error(ident.span(), format!("method `{ident}` borrows from more than one lifetime, which isn't supported yet")));
+    }
+}
+
+/// Custom receivers other than `self: Arc<Self>`, which the future can't borrow from
+fn check_receiver (sig: &Signature, result: &mut Vec<Diagnostic>) {
+    if let Some(FnArg::Typed(PatType { pat, ty, .. })) = sig.inputs.first() {
+        if matches!(&**pat, Pat::Ident(PatIdent { ident, .. }) if ident == "self") && !is_arc_self(ty) {
+            result.push(Diagnostic::warning(ty.span(), format!(
+                "method `{}` has a custom receiver, which the generated future doesn't account for (i.e. it isn't bounded by `Self`'s lifetime)",
+                sig.ident
+            )));
+        }
+    }
+}
+
+/// Types that can't have their `Self` replaced inside the free type alias of a default implementation
+fn check_default (sig: &Signature, result: &mut Vec<Diagnostic>) {
+    struct Unsupported<'a>(&'a Ident, &'a mut Vec<Diagnostic>);
+
+    impl<'ast> Visit<'ast> for Unsupported<'_> {
+        fn visit_type (&mut self, ty: &'ast Type) {
+            if let Type::Verbatim(tokens) = ty {
+                self.1.push(Diagnostic::error(tokens.span(), format!("type `{tokens}` isn't supported in the default implementation of method `{}`", self.0)));
+            }
+            visit::visit_type(self, ty)
+        }
+    }
+
+    Unsupported(&sig.ident, result).visit_signature(sig);
+}
+
+/// Lifetimes the arguments of a method borrow from, outside of higher-ranked contexts
+#[derive(Default)]
+struct Lifetimes {
+    named: HashSet<String>,
+    elided: usize,
+}
+
+impl<'ast> Visit<'ast> for Lifetimes {
+    fn visit_lifetime (&mut self, lifetime: &'ast Lifetime) {
+        match lifetime.ident.to_string().as_str() {
+            "static" => {},
+            "_" => self.elided += 1,
+            name => { self.named.insert(name.to_string()); }
+        }
+    }
+
+    fn visit_type_reference (&mut self, ty: &'ast TypeReference) {
+        if ty.lifetime.is_none() {
+            self.elided += 1;
+        }
+        visit::visit_type_reference(self, ty)
+    }
+
+    fn visit_type_bare_fn (&mut self, _: &'ast TypeBareFn) {}
+    fn visit_parenthesized_generic_arguments (&mut self, _: &'ast ParenthesizedGenericArguments) {}
+}
+
+/// `Arc<Self>` (under any path)
+pub fn is_arc_self (ty: &Type) -> bool {
+    let segment = match ty {
+        Type::Path(TypePath { qself: None, path }) => match path.segments.last() {
+            Some(x) if x.ident == "Arc" => x,
+            _ => return false
+        },
+        _ => return false
+    };
+
+    return match &segment.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => matches!(
+            args.args.first(),
+            Some(GenericArgument::Type(Type::Path(TypePath { qself: None, path }))) if path.is_ident("Self")
+        ),
+        _ => false
+    }
+}
+
+#[inline]
+fn is_async (sig: &Signature) -> bool {
+    return sig.asyncness.is_some() || returns_future(&sig.output)
+}
@@ -0,0 +1,173 @@
+//! Expands every `#[async_trait_def]` trait and `#[async_trait_impl]` impl of a source file or crate,
+//! reporting the constructs the macros don't support before having to compile it.
+//! The `migrate` subcommand rewrites `#[async_trait]` traits and impls into them instead.
+
+use std::{path::{Path, PathBuf}, process::ExitCode, panic::AssertUnwindSafe};
+use proc_macro2::Span;
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use fast_async_trait_codegen::build::{Annotated, visit_annotated};
+
+mod check;
+use check::*;
+
+mod migrate;
+
+const USAGE: &str = "usage: fast-async-trait-cli [--check] <file or crate directory>...
+       fast-async-trait-cli migrate [--dry-run] <file or crate directory>...
+
+options:
+    --check    only report problems, without printing the expansions";
+
+fn main () -> ExitCode {
+    let mut print = true;
+    let mut paths = Vec::new();
+
+    let mut args = std::env::args_os().skip(1).peekable();
+    if args.peek().is_some_and(|x| x == "migrate") {
+        args.next();
+        return migrate::run(args)
+    }
+
+    for arg in args {
+        match arg.to_str() {
+            Some("--check") => print = false,
+            Some("-h" | "--help") => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS
+            },
+            Some(x) if x.starts_with('-') => {
+                eprintln!("unknown option `{x}`\n\n{USAGE}");
+                return ExitCode::from(2)
+            },
+            _ => paths.push(PathBuf::from(arg))
+        }
+    }
+
+    if paths.is_empty() {
+        eprintln!("{USAGE}");
+        return ExitCode::from(2)
+    }
+
+    let mut files = Vec::new();
+    for path in paths {
+        if let Err(e) = collect_files(&path, &mut files) {
+            eprintln!("{}: error: {e}", path.display());
+            return ExitCode::FAILURE
+        }
+    }
+
+    let (mut items, mut errors, mut warnings) = (0usize, 0usize, 0usize);
+    for file in files {
+        for Diagnostic { level, span, message } in process_file(&file, print, &mut items) {
+            let start = span.start();
+            let level = match level {
+                Level::Error => {
+                    errors += 1;
+                    "error"
+                },
+                Level::Warning => {
+                    warnings += 1;
+                    "warning"
+                }
+            };
+
+            eprintln!("{}:{}:{}: {level}: {message}", file.display(), start.line, start.column + 1);
+        }
+    }
+
+    eprintln!("{items} annotated item(s) checked: {errors} error(s), {warnings} warning(s)");
+    return match errors {
+        0 => ExitCode::SUCCESS,
+        _ => ExitCode::FAILURE
+    }
+}
+
+/// Expands (and prints) every annotated item of `path`, returning the problems found
+fn process_file (path: &Path, print: bool, count: &mut usize) -> Vec<Diagnostic> {
+    let source = match std::fs::read_to_string(path) {
+        Ok(x) => x,
+        Err(e) => return vec![Diagnostic::error(Span::call_site(), e.to_string())]
+    };
+
+    let file = match syn::parse_file(&source) {
+        Ok(x) => x,
+        Err(e) => return vec![Diagnostic::error(e.span(), e.to_string())]
+    };
+
+    let mut result = Vec::new();
+    visit_annotated(&file.items, &mut |item| {
+        *count += 1;
+        result.extend(check_item(&item));
+
+        let (name, span) = match &item {
+            Annotated::Def(_, x) => (format!("trait {}", x.ident), x.ident.span()),
+            Annotated::Impl(_, x) => (format!("impl {}", describe_impl(x)), x.self_ty.span())
+        };
+
+        // panics are reported as diagnostics, so the default hook only stays silent while expanding
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(|_| {}));
+        let expanded = std::panic::catch_unwind(AssertUnwindSafe(|| item.expand()));
+        std::panic::set_hook(hook);
+
+        match expanded {
+            Ok(tokens) => {
+                if print {
+                    let pretty = match syn::parse2::<syn::File>(tokens.clone()) {
+                        Ok(file) => prettyplease::unparse(&file),
+                        Err(_) => tokens.to_string()
+                    };
+                    println!("// {name} ({}:{})\n{pretty}", path.display(), span.start().line);
+                }
+                result.extend(compile_errors(tokens));
+            },
+
+            Err(payload) => {
+                let message = payload.downcast_ref::<&str>().map(|x| x.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+                result.push(Diagnostic::error(span, format!("expansion of `{name}` panicked: {message}")));
+            }
+        }
+    });
+
+    return result
+}
+
+fn describe_impl (item: &syn::ItemImpl) -> String {
+    let self_ty = item.self_ty.to_token_stream();
+    return match &item.trait_ {
+        Some((_, path, _)) => format!("{} for {self_ty}", path.to_token_stream()),
+        None => self_ty.to_string()
+    }
+}
+
+/// Collects the Rust files of `path`: the file itself, the `src` directory of a crate or every file inside a directory
+fn collect_files (path: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if path.is_file() {
+        files.push(path.to_path_buf());
+        return Ok(())
+    }
+
+    let dir = match path.join("Cargo.toml").is_file() && path.join("src").is_dir() {
+        true => path.join("src"),
+        false => path.to_path_buf()
+    };
+
+    let mut entries = std::fs::read_dir(&dir)?
+        .map(|x| x.map(|x| x.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort();
+
+    for entry in entries {
+        let hidden = entry.file_name().and_then(|x| x.to_str()).is_some_and(|x| x.starts_with('.') || x == "target");
+        if entry.is_dir() && !hidden {
+            collect_files(&entry, files)?;
+        } else if entry.extension().is_some_and(|x| x == "rs") {
+            files.push(entry);
+        }
+    }
+
+    return Ok(())
+}
@@ -0,0 +1,405 @@
+//! `migrate` subcommand: rewrites the `#[async_trait]` traits and implementations of a crate into `#[async_trait_def]` and `#[async_trait_impl]` ones,
+//! skipping (and reporting) the traits that use constructs the macros can't handle yet.
+
+use std::{collections::{HashMap, HashSet}, ffi::OsString, path::{Path, PathBuf}, process::ExitCode};
+use proc_macro2::{Span, LineColumn};
+use quote::ToTokens;
+use syn::{*, spanned::Spanned, visit::Visit};
+use crate::{check::*, collect_files};
+
+const USAGE: &str = "usage: fast-async-trait-cli migrate [--dry-run] <file or crate directory>...
+
+options:
+    --dry-run    only report what would be rewritten, without writing any file";
+
+const FEATURES: &[&str] = &["type_alias_impl_trait", "impl_trait_in_assoc_type"];
+
+pub fn run (args: impl Iterator<Item = OsString>) -> ExitCode {
+    let mut write = true;
+    let mut paths = Vec::new();
+
+    for arg in args {
+        match arg.to_str() {
+            Some("--dry-run") => write = false,
+            Some("-h" | "--help") => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS
+            },
+            Some(x) if x.starts_with('-') => {
+                eprintln!("unknown option `{x}`\n\n{USAGE}");
+                return ExitCode::from(2)
+            },
+            _ => paths.push(PathBuf::from(arg))
+        }
+    }
+
+    if paths.is_empty() {
+        eprintln!("{USAGE}");
+        return ExitCode::from(2)
+    }
+
+    let mut failed = false;
+    for path in paths {
+        let (roots, manifest) = match path.join("Cargo.toml").is_file() && path.join("src").is_dir() {
+            true => (["lib.rs", "main.rs"].iter().map(|x| path.join("src").join(x)).filter(|x| x.is_file()).collect(), Some(path.join("Cargo.toml"))),
+            false => (vec![path.clone()], None)
+        };
+
+        let mut files = Vec::new();
+        if let Err(e) = collect_files(&path, &mut files) {
+            eprintln!("{}: error: {e}", path.display());
+            return ExitCode::FAILURE
+        }
+
+        failed |= migrate_crate(&files, &roots, manifest.as_deref(), write);
+    }
+
+    return match failed {
+        true => ExitCode::FAILURE,
+        false => ExitCode::SUCCESS
+    }
+}
+
+struct SourceFile {
+    path: PathBuf,
+    source: String,
+    file: syn::File,
+}
+
+/// Item annotated with `#[async_trait]`
+struct Annotation {
+    file: usize,
+    /// The attribute itself
+    span: Span,
+    /// Name of the trait (or of the implemented trait)
+    trait_ident: Option<Ident>,
+    is_impl: bool,
+    /// Options of the migrated trait's `#[async_trait_def]`, `None` if `compat` can't be used
+    options: Option<&'static str>,
+    problems: Vec<Diagnostic>,
+}
+
+/// Everything found by [`Collector`] across the files of a crate
+#[derive(Default)]
+struct Collected {
+    annotations: Vec<Annotation>,
+    /// `use async_trait::async_trait;` items, by file
+    imports: Vec<(usize, Span)>,
+    /// Trait objects, by name of the trait
+    trait_objects: Vec<(usize, Span, Ident)>,
+}
+
+/// Migrates every file of a crate, returning whether any trait had to be skipped
+fn migrate_crate (paths: &[PathBuf], roots: &[PathBuf], manifest: Option<&Path>, write: bool) -> bool {
+    let mut files = Vec::new();
+    let mut diagnostics = Vec::new();
+
+    for path in paths {
+        let source = match std::fs::read_to_string(path) {
+            Ok(x) => x,
+            Err(e) => {
+                report(path, &Diagnostic::error(Span::call_site(), e.to_string()));
+                continue
+            }
+        };
+
+        match syn::parse_file(&source) {
+            Ok(file) => files.push(SourceFile { path: path.clone(), source, file }),
+            Err(e) => report(path, &Diagnostic::error(e.span(), e.to_string()))
+        }
+    }
+
+    let mut collected = Collected::default();
+    for (i, file) in files.iter().enumerate() {
+        Collector { file: i, result: &mut collected }.visit_file(&file.file);
+    }
+
+    // a trait is only migrated if neither itself nor its implementations have any problem
+    let defined = collected.annotations.iter()
+        .filter(|x| !x.is_impl)
+        .filter_map(|x| x.trait_ident.as_ref().map(|ident| ident.to_string()))
+        .collect::<HashSet<_>>();
+
+    let mut skipped = HashSet::new();
+    for annotation in &collected.annotations {
+        if let Some(ident) = &annotation.trait_ident {
+            let name = ident.to_string();
+            if defined.contains(&name) && annotation.problems.iter().any(|x| matches!(x.level, Level::Error)) {
+                skipped.insert(name);
+            }
+        }
+    }
+
+    let compat = collected.annotations.iter()
+        .filter(|x| !x.is_impl && x.options.is_some())
+        .filter_map(|x| x.trait_ident.as_ref().map(|ident| ident.to_string()))
+        .collect::<HashSet<_>>();
+
+    let mut edits = HashMap::<usize, Vec<(usize, usize, String)>>::new();
+    let (mut traits, mut impls) = (0usize, 0usize);
+
+    for annotation in &collected.annotations {
+        let Annotation { file, span, trait_ident, is_impl, options, problems } = annotation;
+        diagnostics.extend(problems.iter().map(|x| (*file, x.clone())));
+
+        let name = trait_ident.as_ref().map(|x| x.to_string());
+        let replacement = match (is_impl, &name) {
+            (_, Some(name)) if skipped.contains(name) => {
+                if !is_impl {
+                    diagnostics.push((*file, Diagnostic::warning(*span, format!("trait `{name}` (and its implementations) keeps `#[async_trait]`"))));
+                }
+                continue
+            },
+            (false, _) => {
+                traits += 1;
+                match options {
+                    Some(options) => format!("#[fast_async_trait::async_trait_def({options})]"),
+                    None => String::from("#[fast_async_trait::async_trait_def]")
+                }
+            },
+            (true, Some(name)) if defined.contains(name) => {
+                impls += 1;
+                String::from("#[fast_async_trait::async_trait_impl]")
+            },
+            (true, _) => {
+                let message = match &name {
+                    Some(name) => format!("trait `{name}` isn't defined in this crate, so its implementation keeps `#[async_trait]`"),
+                    None => String::from("inherent implementations can't be migrated, so this one keeps `#[async_trait]`")
+                };
+                diagnostics.push((*file, Diagnostic::warning(*span, message)));
+                continue
+            }
+        };
+
+        let source = &files[*file].source;
+        edits.entry(*file).or_default().push((offset(source, span.start()), offset(source, span.end()), replacement));
+    }
+
+    // the import is only removed once nothing uses it
+    for (file, span) in &collected.imports {
+        let migrated = edits.get(file).map_or(0, Vec::len);
+        let annotated = collected.annotations.iter().filter(|x| x.file == *file).count();
+        if migrated == annotated {
+            let source = &files[*file].source;
+            let end = offset(source, span.end());
+            let end = source[end..].strip_prefix('\n').map_or(end, |_| end + 1);
+            edits.entry(*file).or_default().push((offset(source, span.start()), end, String::new()));
+        }
+    }
+
+    for (file, span, ident) in &collected.trait_objects {
+        let name = ident.to_string();
+        if defined.contains(&name) && !skipped.contains(&name) {
+            let message = match compat.contains(&name) {
+                true => format!("`{name}` won't be object safe once migrated: use `dyn Dyn{name}` instead"),
+                false => format!("`{name}` won't be object safe once migrated, and can't get an object safe counterpart with the `compat` option")
+            };
+            diagnostics.push((*file, Diagnostic::warning(*span, message)));
+        }
+    }
+
+    if traits + impls > 0 {
+        for (i, file) in files.iter().enumerate() {
+            if roots.contains(&file.path) {
+                if let Some(edit) = feature_edit(&file.source, &file.file) {
+                    edits.entry(i).or_default().push(edit);
+                }
+            }
+        }
+    }
+
+    diagnostics.sort_by_key(|(file, x)| (*file, x.span.start().line, x.span.start().column));
+    for (file, diagnostic) in &diagnostics {
+        report(&files[*file].path, diagnostic);
+    }
+
+    for (file, mut edits) in edits {
+        let SourceFile { path, source, .. } = &files[file];
+        edits.sort_by_key(|(start, _, _)| core::cmp::Reverse(*start));
+
+        let mut result = source.clone();
+        for (start, end, replacement) in edits {
+            result.replace_range(start..end, &replacement);
+        }
+
+        match write {
+            true => if let Err(e) = std::fs::write(path, result) {
+                report(path, &Diagnostic::error(Span::call_site(), e.to_string()));
+            },
+            false => eprintln!("{}: would be rewritten", path.display())
+        }
+    }
+
+    if let Some(manifest) = manifest.filter(|_| traits + impls > 0) {
+        if !std::fs::read_to_string(manifest).is_ok_and(|x| x.contains("fast_async_trait")) {
+            eprintln!("{}: warning: `fast_async_trait` has to be added to the dependencies", manifest.display());
+        }
+    }
+
+    eprintln!("{traits} trait(s) and {impls} implementation(s) migrated, {} trait(s) skipped", skipped.len());
+    return !skipped.is_empty()
+}
+
+#[inline]
+fn report (path: &Path, Diagnostic { level, span, message }: &Diagnostic) {
+    let start = span.start();
+    let level = match level {
+        Level::Error => "error",
+        Level::Warning => "warning"
+    };
+    eprintln!("{}:{}:{}: {level}: {message}", path.display(), start.line, start.column + 1);
+}
+
+/// Byte offset of a location inside `source`
+fn offset (source: &str, LineColumn { line, column }: LineColumn) -> usize {
+    let line_start = source.split_inclusive('\n').take(line - 1).map(str::len).sum::<usize>();
+    let column = source[line_start..].char_indices().nth(column).map_or(source.len() - line_start, |(i, _)| i);
+    return line_start + column
+}
+
+/// Adds the nightly features the macros need to the crate's root, merging them into an existing `#![feature(..)]`
+fn feature_edit (source: &str, file: &syn::File) -> Option<(usize, usize, String)> {
+    let inner = file.attrs.iter().filter(|x| matches!(x.style, AttrStyle::Inner(_))).collect::<Vec<_>>();
+
+    for attr in &inner {
+        if !attr.path.is_ident("feature") {
+            continue
+        }
+
+        let mut features = match attr.parse_meta() {
+            Ok(Meta::List(list)) => list.nested.iter().map(|x| x.to_token_stream().to_string()).collect::<Vec<_>>(),
+            _ => continue
+        };
+        let missing = FEATURES.iter().filter(|x| !features.iter().any(|y| y == *x)).collect::<Vec<_>>();
+        if missing.is_empty() {
+            return None
+        }
+
+        features.extend(missing.into_iter().map(|x| x.to_string()));
+        let span = attr.span();
+        return Some((offset(source, span.start()), offset(source, span.end()), format!("#![feature({})]", features.join(", "))))
+    }
+
+    let attr = format!("#![feature({})]", FEATURES.join(", "));
+    return match inner.last() {
+        Some(last) => {
+            let end = offset(source, last.span().end());
+            Some((end, end, format!("\n{attr}")))
+        },
+        None => Some((0, 0, format!("{attr}\n\n")))
+    }
+}
+
+struct Collector<'a> {
+    file: usize,
+    result: &'a mut Collected,
+}
+
+impl<'ast> Visit<'ast> for Collector<'_> {
+    fn visit_item_trait (&mut self, item: &'ast ItemTrait) {
+        if let Some(attr) = item.attrs.iter().find(|x| is_async_trait(&x.path)) {
+            let mut problems = Vec::new();
+            let mut custom_receiver = None;
+            for item in &item.items {
+                if let TraitItem::Method(TraitItemMethod { sig, .. }) = item {
+                    check_signature(sig, &mut problems);
+                    custom_receiver = custom_receiver.or_else(|| match sig.inputs.first() {
+                        Some(FnArg::Typed(PatType { pat, .. })) if matches!(&**pat, Pat::Ident(PatIdent { ident, .. }) if ident == "self") => Some(&sig.ident),
+                        _ => None
+                    });
+                }
+            }
+
+            // `compat` keeps the futures `Send` (unless `local`), but can't forward custom receivers
+            let local = attr.tokens.to_string().replace(' ', "") == "(?Send)";
+            let options = match (custom_receiver, local) {
+                (None, false) => Some("compat"),
+                (None, true) => Some("compat(local)"),
+                (Some(method), false) => {
+                    problems.push(Diagnostic::warning(attr_span(attr), format!(
+                        "`compat` can't be used since method `{method}` has a custom receiver, so the futures of `{}` won't be required to be `Send` anymore", item.ident
+                    )));
+                    None
+                },
+                (Some(_), true) => None
+            };
+
+            self.result.annotations.push(Annotation {
+                file: self.file,
+                span: attr_span(attr),
+                trait_ident: Some(item.ident.clone()),
+                is_impl: false,
+                options,
+                problems,
+            });
+        }
+        visit::visit_item_trait(self, item)
+    }
+
+    fn visit_item_impl (&mut self, item: &'ast ItemImpl) {
+        if let Some(attr) = item.attrs.iter().find(|x| is_async_trait(&x.path)) {
+            let mut problems = Vec::new();
+            for item in &item.items {
+                if let ImplItem::Method(ImplItemMethod { sig, .. }) = item {
+                    check_signature(sig, &mut problems);
+                }
+            }
+
+            self.result.annotations.push(Annotation {
+                file: self.file,
+                span: attr_span(attr),
+                trait_ident: item.trait_.as_ref().and_then(|(_, path, _)| path.segments.last()).map(|x| x.ident.clone()),
+                is_impl: true,
+                options: None,
+                problems,
+            });
+        }
+        visit::visit_item_impl(self, item)
+    }
+
+    fn visit_item_use (&mut self, item: &'ast ItemUse) {
+        let tree = item.tree.to_token_stream().to_string().replace(' ', "");
+        if tree == "async_trait::async_trait" {
+            self.result.imports.push((self.file, item.span()));
+        }
+    }
+
+    fn visit_type_trait_object (&mut self, ty: &'ast TypeTraitObject) {
+        for bound in &ty.bounds {
+            if let TypeParamBound::Trait(TraitBound { path, .. }) = bound {
+                if let Some(segment) = path.segments.last() {
+                    self.result.trait_objects.push((self.file, ty.span(), segment.ident.clone()));
+                }
+            }
+        }
+        visit::visit_type_trait_object(self, ty)
+    }
+}
+
+/// `#[async_trait]` or `#[async_trait::async_trait]`
+fn is_async_trait (path: &syn::Path) -> bool {
+    let mut segments = path.segments.iter().map(|x| x.ident.to_string());
+    return matches!(
+        (segments.next().as_deref(), segments.next().as_deref(), segments.next()),
+        (Some("async_trait"), None | Some("async_trait"), None)
+    )
+}
+
+#[inline]
+fn attr_span (attr: &Attribute) -> Span {
+    return attr.pound_token.span.join(attr.bracket_token.span).unwrap_or_else(|| attr.span())
+}
+
+/// Reports the async methods the macros can't handle yet: those with a custom receiver other than `self: Arc<Self>`, or borrowing from more than one lifetime
+fn check_signature (sig: &Signature, problems: &mut Vec<Diagnostic>) {
+    if sig.asyncness.is_none() {
+        return
+    }
+
+    if let Some(FnArg::Typed(PatType { pat, ty, .. })) = sig.inputs.first() {
+        if matches!(&**pat, Pat::Ident(PatIdent { ident, .. }) if ident == "self") && !is_arc_self(ty) {
+            problems.push(Diagnostic::error(ty.span(), format!("method `{}` has a custom receiver (`{}`), which isn't supported yet", sig.ident, ty.to_token_stream().to_string().replace(' ', ""))));
+        }
+    }
+    check_lifetimes(sig, problems);
+}
@@ -0,0 +1,19 @@
+use std::process::Command;
+
+#[test]
+fn check () {
+    let output = Command::new(env!("CARGO_BIN_EXE_fast-async-trait-cli"))
+        .args(["--check", "tests/fixtures/check.rs"])
+        .output()
+        .unwrap();
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!output.status.success(), "{stderr}");
+
+    // the trait's and the implementation's
+    assert_eq!(stderr.matches("error: method `get` borrows from more than one lifetime").count(), 2, "{stderr}");
+    assert_eq!(stderr.matches("warning: method `pinned` has a custom receiver").count(), 2, "{stderr}");
+    assert!(!stderr.contains("method `len`"), "{stderr}");
+    assert!(!stderr.contains("method `shared`"), "{stderr}");
+    assert!(stderr.contains("2 annotated item(s) checked"), "{stderr}");
+}
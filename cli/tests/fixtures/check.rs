@@ -0,0 +1,28 @@
+use std::sync::Arc;
+
+#[async_trait_def]
+pub trait Storage {
+    async fn get (&self, key: &str) -> Option<Vec<u8>>;
+    async fn len (&self) -> usize;
+    async fn shared (self: Arc<Self>) -> usize;
+    async fn pinned (self: Pin<&mut Self>) -> usize;
+}
+
+#[async_trait_impl]
+impl Storage for Memory {
+    async fn get (&self, key: &str) -> Option<Vec<u8>> {
+        return self.map.get(key).cloned()
+    }
+
+    async fn len (&self) -> usize {
+        return self.map.len()
+    }
+
+    async fn shared (self: Arc<Self>) -> usize {
+        return self.map.len()
+    }
+
+    async fn pinned (self: Pin<&mut Self>) -> usize {
+        return self.map.len()
+    }
+}
@@ -0,0 +1,13 @@
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait Lookup {
+    async fn find (&self, key: &str) -> Option<u8>;
+}
+
+#[async_trait]
+impl Lookup for Vec<u8> {
+    async fn find (&self, key: &str) -> Option<u8> {
+        return self.get(key.len()).copied()
+    }
+}
@@ -0,0 +1,38 @@
+//! Crate migrated by the tests of the `migrate` subcommand
+#![deny(missing_docs)]
+
+use std::sync::Arc;
+use async_trait::async_trait;
+
+mod legacy;
+
+/// Key-value storage
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Value stored under `key`
+    async fn get (&self, key: String) -> Option<Vec<u8>>;
+}
+
+/// Storage without any value
+pub struct Empty;
+
+#[async_trait]
+impl Storage for Empty {
+    async fn get (&self, _key: String) -> Option<Vec<u8>> {
+        return None
+    }
+}
+
+/// Storage shared between tasks
+#[async_trait]
+pub trait Shared: Send + Sync {
+    /// Number of stored values, counted in the background
+    async fn count (self: Arc<Self>) -> usize;
+}
+
+/// Storage bound to the current thread
+#[async_trait(?Send)]
+pub trait Local {
+    /// Value stored under `key`
+    async fn get (&self, key: String) -> Option<Vec<u8>>;
+}
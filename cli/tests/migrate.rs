@@ -0,0 +1,50 @@
+use std::{fs, path::Path, process::Command};
+
+fn copy_dir (from: &Path, to: &Path) {
+    fs::create_dir_all(to).unwrap();
+    for entry in fs::read_dir(from).unwrap() {
+        let entry = entry.unwrap();
+        match entry.file_type().unwrap().is_dir() {
+            true => copy_dir(&entry.path(), &to.join(entry.file_name())),
+            false => { fs::copy(entry.path(), to.join(entry.file_name())).unwrap(); }
+        }
+    }
+}
+
+#[test]
+fn migrate () {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("migrate");
+    let _ = fs::remove_dir_all(&dir);
+    copy_dir(Path::new("tests/fixtures/migrate"), &dir);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_fast-async-trait-cli"))
+        .arg("migrate")
+        .arg(&dir)
+        .output()
+        .unwrap();
+
+    // the skipped trait fails the migration
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!output.status.success(), "{stderr}");
+
+    let lib = fs::read_to_string(dir.join("src/lib.rs")).unwrap();
+    assert!(lib.contains("#![deny(missing_docs)]\n#![feature(type_alias_impl_trait, impl_trait_in_assoc_type)]\n"), "{lib}");
+    assert!(!lib.contains("use async_trait::async_trait;"), "{lib}");
+    assert!(!lib.contains("#[async_trait]"), "{lib}");
+    assert!(lib.contains("#[fast_async_trait::async_trait_def(compat)]\npub trait Storage"), "{lib}");
+    assert!(lib.contains("#[fast_async_trait::async_trait_impl]\nimpl Storage for Empty"), "{lib}");
+    assert!(lib.contains("#[fast_async_trait::async_trait_def(compat(local))]\npub trait Local"), "{lib}");
+
+    // `compat` can't forward `self: Arc<Self>`, which the macros support otherwise
+    assert!(lib.contains("#[fast_async_trait::async_trait_def]\npub trait Shared"), "{lib}");
+    assert!(stderr.contains("warning: `compat` can't be used since method `count` has a custom receiver, so the futures of `Shared` won't be required to be `Send` anymore"), "{stderr}");
+
+    // `find` borrows from both `self` and `key`
+    let legacy = fs::read_to_string(dir.join("src/legacy.rs")).unwrap();
+    assert_eq!(legacy, fs::read_to_string("tests/fixtures/migrate/src/legacy.rs").unwrap());
+    assert!(stderr.contains("error: method `find` borrows from more than one lifetime"), "{stderr}");
+    assert!(stderr.contains("warning: trait `Lookup` (and its implementations) keeps `#[async_trait]`"), "{stderr}");
+
+    assert!(stderr.contains("warning: `fast_async_trait` has to be added to the dependencies"), "{stderr}");
+    assert!(stderr.contains("3 trait(s) and 1 implementation(s) migrated, 1 trait(s) skipped"), "{stderr}");
+}
@@ -0,0 +1,280 @@
+use proc_macro2::Span;
+use syn::{*, punctuated::Punctuated, parse::{Parse, ParseStream}};
+
+/// Value of a macro option: `name`, `name = "value"` or `name(nested, options)`
+pub enum MacroArgValue {
+    Flag,
+    Lit(Lit),
+    List(token::Paren, MacroArgs),
+}
+
+pub struct MacroArg {
+    pub ident: Ident,
+    pub value: MacroArgValue,
+}
+
+/// Comma-separated list of options given to a macro
+pub struct MacroArgs {
+    pub args: Punctuated<MacroArg, Token![,]>,
+}
+
+/// Options accepted by a macro (or by a nested option list), by shape
+pub struct MacroArgSpec<'a> {
+    pub flags: &'a [&'a str],
+    pub values: &'a [&'a str],
+    pub lists: &'a [(&'a str, MacroArgSpec<'a>)],
+    /// Pairs of options that can't be given together, and why
+    pub conflicts: &'a [(&'a str, &'a str, &'a str)],
+}
+
+/// Options of `#[async_trait_def]`
+#[derive(Default)]
+pub struct DefArgs {
+    /// Generates an object safe counterpart of the trait, with boxed futures
+    pub compat: Option<CompatArgs>,
+    /// Implements the trait for every implementor of the given equivalent trait, written with native `async fn`s
+    pub native: Option<Path>,
+    /// Requires the futures of every async method to be `'static`, like `#[detached]` does for a single method
+    pub static_futures: bool,
+    /// Dumps the pretty-printed expansion
+    pub debug: bool,
+}
+
+/// Options of `#[async_trait_def(compat(..))]`
+#[derive(Default)]
+pub struct CompatArgs {
+    /// Doesn't require the futures to be `Send`, like `#[async_trait(?Send)]`
+    pub local: bool,
+}
+
+/// Options of `#[async_trait_impl]`
+#[derive(Default)]
+pub struct ImplArgs {
+    /// Skips checking the implementation against the trait's marker, for traits not defined with `#[async_trait_def]`
+    pub unchecked: bool,
+    /// Leaves the async methods untouched, for traits written with native `async fn`s
+    pub native: bool,
+    /// Fails compilation if the future of an async method is larger than the given number of bytes
+    pub max_future_size: Option<usize>,
+    /// Dumps the pretty-printed expansion
+    pub debug: bool,
+}
+
+const DEF_ARGS: MacroArgSpec<'static> = MacroArgSpec {
+    flags: &["compat", "static_futures", "debug"],
+    values: &["native"],
+    lists: &[("compat", COMPAT_ARGS)],
+    conflicts: &[("compat", "native", "the futures of the native trait can't be required to be `Send`")],
+};
+
+const COMPAT_ARGS: MacroArgSpec<'static> = MacroArgSpec {
+    flags: &["local"],
+    values: &[],
+    lists: &[],
+    conflicts: &[],
+};
+
+const IMPL_ARGS: MacroArgSpec<'static> = MacroArgSpec {
+    flags: &["unchecked", "native", "debug"],
+    values: &["max_future_size"],
+    lists: &[],
+    conflicts: &[
+        ("native", "unchecked", "native implementations are never checked"),
+        ("native", "max_future_size", "the futures of native implementations can't be named")
+    ],
+};
+
+impl MacroArg {
+    #[inline]
+    pub fn span (&self) -> Span {
+        return match &self.value {
+            MacroArgValue::Flag => self.ident.span(),
+            MacroArgValue::Lit(lit) => self.ident.span().join(lit.span()).unwrap_or_else(|| self.ident.span()),
+            MacroArgValue::List(paren, _) => self.ident.span().join(paren.span).unwrap_or_else(|| self.ident.span()),
+        }
+    }
+}
+
+impl MacroArgSpec<'_> {
+    #[inline]
+    fn contains (&self, name: &Ident) -> bool {
+        return self.flags.iter().chain(self.values).chain(self.lists.iter().map(|(x, _)| x)).any(|x| name == x)
+    }
+
+    fn names (&self) -> String {
+        let names = self.flags.iter().map(|x| format!("`{x}`"))
+            .chain(self.values.iter().map(|x| format!("`{x} = ...`")))
+            .chain(self.lists.iter().map(|(x, _)| format!("`{x}(...)`")))
+            .collect::<Vec<_>>();
+
+        return match names.is_empty() {
+            true => String::from("no options are supported"),
+            false => format!("expected one of: {}", names.join(", "))
+        }
+    }
+}
+
+impl MacroArgs {
+    /// Returns whether the (previously validated) flag is set
+    #[inline]
+    pub fn flag (&self, name: &str) -> bool {
+        return self.args.iter().any(|x| x.ident == name)
+    }
+
+    /// Returns the (previously validated) option, if given
+    #[inline]
+    pub fn get (&self, name: &str) -> Option<&MacroArgValue> {
+        return self.args.iter().find(|x| x.ident == name).map(|x| &x.value)
+    }
+
+    /// Checks that every option is known and has the expected shape, that no option is given more than once, and that no conflicting options are given together
+    pub fn validate (&self, spec: &MacroArgSpec) -> Result<()> {
+        let mut result = Ok(());
+
+        for (i, arg) in self.args.iter().enumerate() {
+            let name = &arg.ident;
+            let e = if !spec.contains(name) {
+                Err(Error::new(name.span(), format!("unknown option `{name}`, {}", spec.names())))
+            } else if self.args.iter().take(i).any(|x| &x.ident == name) {
+                Err(Error::new(name.span(), format!("duplicate option `{name}`")))
+            } else {
+                match &arg.value {
+                    MacroArgValue::Flag if spec.flags.iter().any(|x| name == x) => Ok(()),
+                    MacroArgValue::Lit(_) if spec.values.iter().any(|x| name == x) => Ok(()),
+                    MacroArgValue::List(_, nested) => match spec.lists.iter().find(|(x, _)| name == x) {
+                        Some((_, nested_spec)) => nested.validate(nested_spec),
+                        None => Err(Error::new(arg.span(), format!("option `{name}` doesn't take a list of options")))
+                    },
+                    _ if spec.flags.iter().any(|x| name == x) => Err(Error::new(arg.span(), format!("option `{name}` doesn't take a value"))),
+                    _ if spec.values.iter().any(|x| name == x) => Err(Error::new(arg.span(), format!("expected `{name} = ...`"))),
+                    _ => Err(Error::new(arg.span(), format!("expected `{name}(...)`")))
+                }
+            };
+
+            if let Err(e) = e {
+                combine(&mut result, e);
+            }
+        }
+
+        for (first, second, reason) in spec.conflicts {
+            let first = self.args.iter().position(|x| x.ident == first);
+            let second = self.args.iter().position(|x| x.ident == second);
+            if let (Some(first), Some(second)) = (first, second) {
+                let (first, second) = (&self.args[first.min(second)], &self.args[first.max(second)]);
+                combine(&mut result, Error::new(second.span(), format!("option `{}` can't be used together with `{}`, since {reason}", second.ident, first.ident)));
+            }
+        }
+
+        return result
+    }
+}
+
+impl Parse for MacroArg {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let ident = input.call(ext::IdentExt::parse_any)?;
+        let value = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            MacroArgValue::Lit(input.parse()?)
+        } else if input.peek(token::Paren) {
+            let content;
+            let paren = parenthesized!(content in input);
+            MacroArgValue::List(paren, content.parse()?)
+        } else {
+            MacroArgValue::Flag
+        };
+
+        Ok(MacroArg { ident, value })
+    }
+}
+
+impl Parse for MacroArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(MacroArgs { args: Punctuated::parse_terminated(input)? })
+    }
+}
+
+impl Parse for DefArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let args: MacroArgs = input.parse()?;
+        args.validate(&DEF_ARGS)?;
+        Ok(DefArgs {
+            compat: args.get("compat").map(|x| match x {
+                MacroArgValue::List(_, nested) => CompatArgs {
+                    local: nested.flag("local"),
+                },
+                _ => CompatArgs::default()
+            }),
+            native: match args.get("native") {
+                Some(MacroArgValue::Lit(Lit::Str(path))) => Some(path.parse()?),
+                Some(MacroArgValue::Lit(lit)) => return Err(Error::new(lit.span(), "expected the path of the native trait, as a string")),
+                _ => None
+            },
+            static_futures: args.flag("static_futures"),
+            debug: args.flag("debug"),
+        })
+    }
+}
+
+impl Parse for ImplArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let args: MacroArgs = input.parse()?;
+        args.validate(&IMPL_ARGS)?;
+        Ok(ImplArgs {
+            unchecked: args.flag("unchecked"),
+            native: args.flag("native"),
+            max_future_size: match args.get("max_future_size") {
+                Some(MacroArgValue::Lit(Lit::Int(size))) => Some(size.base10_parse()?),
+                Some(MacroArgValue::Lit(lit)) => return Err(Error::new(lit.span(), "expected the maximum size of the futures, in bytes")),
+                _ => None
+            },
+            debug: args.flag("debug"),
+        })
+    }
+}
+
+#[inline]
+fn combine (result: &mut Result<()>, e: Error) {
+    match result {
+        Ok(_) => *result = Err(e),
+        Err(prev) => prev.combine(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    const SPEC: MacroArgSpec<'static> = MacroArgSpec {
+        flags: &["first", "second"],
+        values: &["value"],
+        lists: &[("list", MacroArgSpec { flags: &["nested"], values: &[], lists: &[], conflicts: &[] })],
+        conflicts: &[("first", "second", "they exclude each other")],
+    };
+
+    fn error (args: proc_macro2::TokenStream) -> String {
+        return match parse2::<MacroArgs>(args).and_then(|x| x.validate(&SPEC)) {
+            Ok(_) => panic!("the options were accepted"),
+            Err(e) => e.to_string()
+        }
+    }
+
+    #[test]
+    fn invalid_options () {
+        assert_eq!(error(quote!(sned)), "unknown option `sned`, expected one of: `first`, `second`, `value = ...`, `list(...)`");
+        assert_eq!(error(quote!(first, first)), "duplicate option `first`");
+        assert_eq!(error(quote!(first = "yes")), "option `first` doesn't take a value");
+        assert_eq!(error(quote!(value)), "expected `value = ...`");
+        assert_eq!(error(quote!(list(sned))), "unknown option `sned`, expected one of: `nested`");
+        assert!(parse2::<MacroArgs>(quote!(first, value = 1, list(nested))).unwrap().validate(&SPEC).is_ok());
+
+        // the options of both macros are validated
+        assert!(parse2::<DefArgs>(quote!(sned)).is_err_and(|e| e.to_string().starts_with("unknown option `sned`")));
+        assert!(parse2::<ImplArgs>(quote!(sned)).is_err_and(|e| e.to_string().starts_with("unknown option `sned`")));
+    }
+
+    #[test]
+    fn conflicting_options () {
+        assert_eq!(error(quote!(second, value = 1, first)), "option `first` can't be used together with `second`, since they exclude each other");
+    }
+}
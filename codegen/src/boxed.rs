@@ -0,0 +1,143 @@
+use std::collections::HashSet;
+use proc_macro2::{TokenStream, Span};
+use quote::{quote_spanned, ToTokens};
+use syn::{*, punctuated::Punctuated, spanned::Spanned};
+use crate::*;
+
+/// Checks whether the method is annotated with `#[boxed]`
+#[inline]
+pub fn is_boxed (attrs: &[Attribute]) -> bool {
+    return attrs.iter().any(|x| x.path.is_ident("boxed"))
+}
+
+/// Expands an async trait method annotated with `#[boxed]`, which returns a boxed future instead of an associated one
+pub fn define_boxed_fn (extra_bounds: &[TypeParamBound], method: AsyncTraitItemMethod) -> TokenStream {
+    let span = method.sig.span();
+    let used = used_idents(method.to_token_stream());
+    let AsyncTraitItemMethod { mut attrs, sig, default, semi_token } = method;
+    take_attr(&mut attrs, "boxed");
+    let detached = take_attr(&mut attrs, "detached") || extra_bounds.iter().any(|x| matches!(x, TypeParamBound::Lifetime(lt) if lt.ident == "static"));
+
+    let (sig, future, output, bindings) = match boxed_fn(sig, extra_bounds, detached, &used, span) {
+        Ok(x) => x,
+        Err(e) => return e.into_compile_error()
+    };
+
+    let body = default.map(|block| boxed_body(span, &sig, &bindings, &output, &block));
+    let Signature { constness, unsafety, abi, fn_token, ident, generics, inputs, variadic, .. } = sig;
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    return quote_spanned! { span =>
+        #(#attrs)*
+        #constness #unsafety #abi #fn_token #ident #impl_generics (#inputs #variadic) -> #future #where_clause #body #semi_token
+    }
+}
+
+/// Expands an async impl method annotated with `#[boxed]`. If the trait's marker can be reached through `trait_path`, the boxed future is the one it declares,
+/// so that the trait's bounds (i.e. `Send`) don't have to be repeated.
+pub fn impl_boxed_fn (method: ImplItemMethod, trait_path: Option<&Path>) -> TokenStream {
+    let span = method.sig.span();
+    let used = used_idents(method.to_token_stream());
+    let ImplItemMethod { mut attrs, vis, defaultness, sig, block } = method;
+    take_attr(&mut attrs, "boxed");
+    let detached = take_attr(&mut attrs, "detached");
+
+    let (sig, future, output, bindings) = match boxed_fn(sig, &[], detached, &used, span) {
+        Ok(x) => x,
+        Err(e) => return e.into_compile_error()
+    };
+    let future = match trait_path {
+        Some(path) => trait_future(path, &sig.ident, sig.generics.lifetimes().next().map(|x| &x.lifetime), &output),
+        None => future
+    };
+
+    let body = boxed_body(span, &sig, &bindings, &output, &block);
+    let Signature { constness, unsafety, abi, fn_token, ident, generics, inputs, variadic, .. } = sig;
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    return quote_spanned! { span =>
+        #(#attrs)*
+        #vis #defaultness #constness #unsafety #abi #fn_token #ident #impl_generics (#inputs #variadic) -> #future #where_clause #body
+    }
+}
+
+/// Arm of the trait's marker producing the boxed future of a method, given the lifetime it borrows and its output
+pub fn boxed_marker_arm (extra_bounds: &[TypeParamBound], sig: &Signature) -> TokenStream {
+    let ident = &sig.ident;
+    let span = sig.span();
+    let bounds = dyn_bounds(future_bounds(&sig.asyncness, sig.output.clone(), span), extra_bounds);
+    let bounds = bounds.iter().filter(|x| !is_future_bound(x));
+
+    return quote_spanned! { span =>
+        (@boxed #ident [$($lt:lifetime)?] [$output:ty]) => {
+            ::core::pin::Pin<::fast_async_trait::__private::Box<dyn ::core::future::Future<Output = $output> #(+ #bounds)* $(+ $lt)?>>
+        };
+    }
+}
+
+/// Boxed future of a trait method, as declared by the trait's marker
+pub fn trait_future (trait_path: &Path, ident: &Ident, lifetime: Option<&Lifetime>, output: &Type) -> TokenStream {
+    return quote_spanned! { ident.span() =>
+        #trait_path!(@boxed #ident [#lifetime] [#output])
+    }
+}
+
+/// Names the lifetime of the future (as in [`future_generics`]), returning the updated signature, the boxed future, its output and the bindings of the arguments
+fn boxed_fn (mut sig: Signature, extra_bounds: &[TypeParamBound], detached: bool, used: &HashSet<String>, span: Span) -> Result<(Signature, TokenStream, Type, Vec<TokenStream>)> {
+    let bounds = future_bounds(&sig.asyncness, sig.output.clone(), span);
+    let (_, _, output_lt) = future_generics(&mut sig.inputs, &mut sig.generics, used);
+    let bindings = normalize_inputs(sig.inputs.iter_mut());
+
+    let mut bounds = dyn_bounds(bounds, extra_bounds);
+    elide_output(bounds.iter_mut(), output_lt.as_ref())?;
+    if sig.generics.lifetimes().count() > 1 {
+        return Err(Error::new(sig.generics.lifetimes().nth(1).unwrap().span(), "Currently only one lifetime per future is supported"))
+    }
+    if detached {
+        require_static(&sig.ident, &mut sig.generics)?;
+    }
+
+    let output = match future_output(&bounds) {
+        Some(output) if contains_impl_trait(output) => return Err(Error::new_spanned(output, format!("the future of method `{}` can't be boxed, since its output contains an `impl Trait`", sig.ident))),
+        Some(output) => output.clone(),
+        None => return Err(Error::new_spanned(&bounds, format!("the future of method `{}` can't be boxed, since it doesn't name its output", sig.ident)))
+    };
+
+    let lifetime = sig.generics.lifetimes().next().map(|LifetimeDef { lifetime, .. }| quote_spanned! { span => + #lifetime });
+    let future = quote_spanned! { span => ::core::pin::Pin<::fast_async_trait::__private::Box<dyn #bounds #lifetime>> };
+    return Ok((sig, future, output, bindings))
+}
+
+/// Bounds of a boxed future, without lifetimes (the method's lifetime is added instead)
+fn dyn_bounds (bounds: Punctuated<TypeParamBound, Token![+]>, extra_bounds: &[TypeParamBound]) -> Punctuated<TypeParamBound, Token![+]> {
+    let mut result = Punctuated::<TypeParamBound, Token![+]>::new();
+    for bound in bounds.into_iter().chain(extra_bounds.iter().cloned()) {
+        let bound_str = bound.to_token_stream().to_string();
+        if !matches!(bound, TypeParamBound::Lifetime(_)) && !result.iter().any(|x| x.to_token_stream().to_string() == bound_str) {
+            result.push(bound);
+        }
+    }
+    return result
+}
+
+#[inline]
+fn is_future_bound (bound: &TypeParamBound) -> bool {
+    return future_output(&Punctuated::<_, Token![+]>::from_iter([bound.clone()])).is_some()
+}
+
+fn boxed_body (span: Span, sig: &Signature, bindings: &[TokenStream], output: &Type, block: &Block) -> TokenStream {
+    let future = match sig.asyncness {
+        Some(_) => future_body(span, &sig.asyncness, bindings, Some(output), block),
+        None => {
+            let stmts = &block.stmts;
+            quote_spanned! { span => {
+                #(#bindings)*
+                #(#stmts)*
+            }}
+        }
+    };
+
+    return quote_spanned! { span => {
+        return ::fast_async_trait::__private::Box::pin(#future)
+    }}
+}
@@ -0,0 +1,142 @@
+//! Expansion of whole source files, for build scripts that generate code instead of relying on the attribute macros.
+//!
+//! ```ignore
+//! // build.rs
+//! fn main () {
+//!     fast_async_trait_codegen::build::generate("src/traits.in.rs").unwrap();
+//! }
+//!
+//! // src/lib.rs
+//! include!(concat!(env!("OUT_DIR"), "/traits.in.rs"));
+//! ```
+//!
+//! The expansion is plain text, so the names it generates lose the hygiene they have in the macros' expansion. The `__argN` bindings of
+//! pattern arguments avoid the method's other arguments, but would still shadow an item with the same name used inside the method's body.
+
+use std::{path::{Path, PathBuf}, io};
+use proc_macro2::{TokenStream, TokenTree, Delimiter};
+use quote::{quote, ToTokens};
+use syn::*;
+use crate::{async_trait_def, async_trait_impl};
+
+/// Expands every `#[async_trait_def]` trait and `#[async_trait_impl]` impl in `source` (including those inside inline modules)
+/// exactly like the attribute macros would, leaving every other item untouched. The result is pretty-printed.
+/// Inner attributes (`#![...]`) are rejected, since they aren't allowed in `include!`d files.
+pub fn expand_source (source: &str) -> Result<String> {
+    let File { shebang: _, attrs, items } = parse_file(source)?;
+    if let Some(attr) = attrs.first() {
+        return Err(Error::new_spanned(attr, "inner attributes can't be used in an included file, they belong to the including module"))
+    }
+
+    let expanded = expand_items(items);
+
+    return Ok(match parse2::<File>(expanded.clone()) {
+        Ok(file) => prettyplease::unparse(&file),
+        Err(_) => expanded.to_string()
+    })
+}
+
+/// Reads the source file at `path` and expands it (see [`expand_source`]). Parse errors are reported with the file's path and the error's position.
+pub fn expand_file (path: impl AsRef<Path>) -> io::Result<String> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path)?;
+
+    return expand_source(&source).map_err(|e| {
+        let start = e.span().start();
+        io::Error::new(io::ErrorKind::InvalidData, format!("{}:{}:{}: {e}", path.display(), start.line, start.column + 1))
+    })
+}
+
+/// Expands the source file at `path` (relative to the crate's root) into `$OUT_DIR`, with the same file name, so that it can be `include!`d.
+/// Meant to be called from a build script, it also tells cargo to rerun it when the source file changes. Returns the path of the generated file.
+pub fn generate (path: impl AsRef<Path>) -> io::Result<PathBuf> {
+    let path = path.as_ref();
+    let out_dir = std::env::var_os("OUT_DIR")
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "`OUT_DIR` isn't set, `generate` must be called from a build script"))?;
+
+    let file_name = path.file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("`{}` isn't a file", path.display())))?;
+
+    println!("cargo:rerun-if-changed={}", path.display());
+    let expanded = expand_file(path)?;
+
+    let out = PathBuf::from(out_dir).join(file_name);
+    std::fs::write(&out, expanded)?;
+    return Ok(out)
+}
+
+/// Trait or implementation annotated with `#[async_trait_def]` or `#[async_trait_impl]`, along with the annotation's arguments
+pub enum Annotated {
+    Def(TokenStream, ItemTrait),
+    Impl(TokenStream, ItemImpl),
+}
+
+impl Annotated {
+    /// Takes the annotation out of `item`, giving the item back if it isn't annotated
+    #[allow(clippy::result_large_err)]
+    pub fn from_item (item: Item) -> std::result::Result<Self, Item> {
+        return match item {
+            Item::Trait(mut item) => match take_attr(&mut item.attrs, "async_trait_def") {
+                Some(args) => Ok(Self::Def(args, item)),
+                None => Err(Item::Trait(item))
+            },
+
+            Item::Impl(mut item) => match take_attr(&mut item.attrs, "async_trait_impl") {
+                Some(args) => Ok(Self::Impl(args, item)),
+                None => Err(Item::Impl(item))
+            },
+
+            other => Err(other)
+        }
+    }
+
+    /// Expands the item like the attribute macro would
+    pub fn expand (self) -> TokenStream {
+        return match self {
+            Self::Def(args, item) => async_trait_def(args, item.into_token_stream()),
+            Self::Impl(args, item) => async_trait_impl(args, item.into_token_stream())
+        }
+    }
+}
+
+/// Calls `f` with every annotated item in `items`, including those inside inline modules
+pub fn visit_annotated (items: &[Item], f: &mut impl FnMut(Annotated)) {
+    for item in items {
+        match Annotated::from_item(item.clone()) {
+            Ok(annotated) => f(annotated),
+            Err(Item::Mod(ItemMod { content: Some((_, items)), .. })) => visit_annotated(&items, f),
+            Err(_) => {}
+        }
+    }
+}
+
+fn expand_items (items: Vec<Item>) -> TokenStream {
+    return items.into_iter().map(|item| match Annotated::from_item(item) {
+        Ok(annotated) => annotated.expand(),
+
+        Err(Item::Mod(ItemMod { attrs, vis, mod_token, ident, content: Some((_, items)), .. })) => {
+            let items = expand_items(items);
+            quote! {
+                #(#attrs)*
+                #vis #mod_token #ident {
+                    #items
+                }
+            }
+        },
+
+        Err(other) => other.into_token_stream()
+    }).collect()
+}
+
+/// Removes the first attribute whose path ends in `name`, returning its arguments
+fn take_attr (attrs: &mut Vec<Attribute>, name: &str) -> Option<TokenStream> {
+    let idx = attrs.iter().position(|attr| matches!(attr.path.segments.last(), Some(x) if x.ident == name))?;
+    let attr = attrs.remove(idx);
+
+    let mut tokens = attr.tokens.into_iter();
+    return Some(match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Group(group)), None) if group.delimiter() == Delimiter::Parenthesis => group.stream(),
+        (None, _) => TokenStream::new(),
+        (first, second) => first.into_iter().chain(second).chain(tokens).collect()
+    })
+}
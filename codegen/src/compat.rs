@@ -0,0 +1,584 @@
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned, format_ident, ToTokens};
+use syn::{*, punctuated::Punctuated, spanned::Spanned};
+use crate::*;
+
+/// Bounds added to every future of a `compat` trait, so that they can be boxed into the futures of its object safe counterpart
+pub fn compat_bounds (args: &CompatArgs) -> Vec<TypeParamBound> {
+    return match args.local {
+        true => Vec::new(),
+        false => vec![parse_quote! { ::core::marker::Send }]
+    }
+}
+
+/// Requires the receiver of a default async method to be `Sync` (or `Send`, if it isn't taken by reference), since its future has to be `Send`
+pub fn require_send (item: &mut AsyncTraitItem) {
+    if let AsyncTraitItem::Method(AsyncTraitItemMethod { sig, default: Some(_), .. }) = item {
+        if sig.asyncness.is_none() && !returns_future(&sig.output) {
+            return
+        }
+
+        let span = sig.span();
+        let bound = match receiver_kind(&sig.inputs) {
+            "by_ref" => quote_spanned! { span => ::core::marker::Sync },
+            "none" => return,
+            _ => quote_spanned! { span => ::core::marker::Send }
+        };
+        sig.generics.make_where_clause().predicates.push(parse_quote_spanned! { span => Self: #bound });
+    }
+}
+
+/// Trait being mirrored by [`define_compat`]
+pub struct CompatTrait<'a> {
+    pub attrs: &'a [Attribute],
+    pub vis: &'a Visibility,
+    pub unsafety: Option<Token![unsafe]>,
+    pub ident: &'a Ident,
+    pub generics: &'a Generics,
+    pub supertraits: &'a Punctuated<TypeParamBound, Token![+]>,
+    pub items: &'a [AsyncTraitItem],
+    /// Whether the trait has the hidden item added by `#[async_trait_def]` (i.e. it isn't an auto trait)
+    pub impl_required: bool,
+}
+
+/// Items generated for a single item of the trait
+#[derive(Default)]
+struct CompatItem {
+    /// Declaration inside the object safe trait
+    decl: TokenStream,
+    /// Implementation of the object safe trait for implementors of the trait
+    forward: TokenStream,
+    /// Implementation of the trait for `Compat` wrappers of implementors of the object safe trait
+    backward: TokenStream,
+    /// Implementation of the object safe trait for pointers to its trait objects
+    pointer: TokenStream,
+}
+
+/// Emits the object safe counterpart of a `compat` trait (`DynTrait`), whose async methods return boxed futures, implemented for every implementor of the trait.
+/// The trait is in turn implemented for `Compat` wrappers of the implementors of `DynTrait`, which include the pointers (`Box`, `Arc` and `&`) to its trait objects.
+pub fn define_compat (args: &CompatArgs, def: CompatTrait) -> TokenStream {
+    let CompatTrait { attrs, vis, unsafety, ident: trait_ident, generics, supertraits, items, impl_required } = def;
+    let span = trait_ident.span();
+    let dyn_ident = format_ident!("Dyn{trait_ident}", span = span);
+
+    let mut used = used_idents(quote! { #generics #supertraits #(#items)* });
+    used.insert(trait_ident.to_string());
+    let this = format_ident!("{}", unique_name("This", &used), span = span);
+
+    let future = match args.local {
+        true => quote_spanned! { span => ::fast_async_trait::LocalBoxFuture },
+        false => quote_spanned! { span => ::fast_async_trait::BoxFuture }
+    };
+
+    let assoc_types = items.iter()
+        .filter_map(|x| match x {
+            AsyncTraitItem::Type(TraitItemType { ident, .. }) => Some(ident.clone()),
+            _ => None
+        })
+        .collect::<Vec<_>>();
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let ctx = CompatContext {
+        trait_ident,
+        dyn_ident: &dyn_ident,
+        ty_generics: ty_generics.to_token_stream(),
+        this: &this,
+        assoc_types: &assoc_types,
+        trait_type_params: generics.type_params().map(|x| x.ident.clone()).collect(),
+        future,
+    };
+
+    let mut state = CompatState::default();
+    let items = items.iter()
+        .map(|item| compat_item(&ctx, &mut state, item))
+        .collect::<Vec<_>>();
+    let decls = items.iter().map(|x| &x.decl);
+    let forwards = items.iter().map(|x| &x.forward);
+    let backwards = items.iter().map(|x| &x.backward);
+    let errors = &state.errors;
+    let pointers = match state.dyn_incompatible {
+        true => None,
+        false => Some(define_pointers(&ctx, &state, unsafety, generics, supertraits, &items, &used))
+    };
+
+    let trait_attrs = mirrored_attrs(attrs, true).into_iter().filter(|x| !x.path.is_ident("doc"));
+    let doc = format!(
+        " Object safe counterpart of [`{trait_ident}`], whose async methods return boxed futures. It's implemented for every implementor of [`{trait_ident}`],\n\
+        \x20which is in turn implemented by [`Compat`](::fast_async_trait::Compat) wrappers of the implementors of this trait."
+    );
+    let colon_token = match supertraits.is_empty() {
+        true => None,
+        false => Some(<Token![:]>::default())
+    };
+
+    let mut forward_generics = generics.clone();
+    forward_generics.params.push(parse_quote_spanned! { span => #this: ?::core::marker::Sized + #trait_ident #ty_generics });
+    let (forward_impl_generics, _, forward_where) = forward_generics.split_for_impl();
+
+    let mut backward_generics = generics.clone();
+    backward_generics.params.push(match state.sized {
+        true => parse_quote_spanned! { span => #this: #dyn_ident #ty_generics },
+        false => parse_quote_spanned! { span => #this: ?::core::marker::Sized + #dyn_ident #ty_generics }
+    });
+    if !supertraits.is_empty() {
+        backward_generics.make_where_clause().predicates.push(parse_quote_spanned! { span => ::fast_async_trait::Compat<#this>: #supertraits });
+    }
+    if state.borrows_nothing {
+        let predicates = &mut backward_generics.make_where_clause().predicates;
+        predicates.push(parse_quote_spanned! { span => #this: 'static });
+        for param in &ctx.trait_type_params {
+            predicates.push(parse_quote_spanned! { span => #param: 'static });
+        }
+    }
+    let (backward_impl_generics, _, backward_where) = backward_generics.split_for_impl();
+
+    let impl_required = match impl_required {
+        true => Some(crate::marker::impl_required()),
+        false => None
+    };
+
+    return quote_spanned! { span =>
+        #(#trait_attrs)*
+        #[doc = #doc]
+        #vis #unsafety trait #dyn_ident #impl_generics #colon_token #supertraits #where_clause {
+            #(#decls)*
+        }
+
+        #[allow(deprecated)]
+        #unsafety impl #forward_impl_generics #dyn_ident #ty_generics for #this #forward_where {
+            #(#forwards)*
+        }
+
+        #[allow(deprecated)]
+        #unsafety impl #backward_impl_generics #trait_ident #ty_generics for ::fast_async_trait::Compat<#this> #backward_where {
+            #(#backwards)*
+            #impl_required
+        }
+
+        #pointers
+        #(#errors)*
+    }
+}
+
+/// Implements `DynTrait` for `Box`es, `Arc`s and references of its trait objects (including their `Send` and `Send + Sync` variants),
+/// so that they can be wrapped by `Compat`. `Arc`s and references are skipped if some method takes `&mut self`.
+fn define_pointers (ctx: &CompatContext, state: &CompatState, unsafety: Option<Token![unsafe]>, generics: &Generics, supertraits: &Punctuated<TypeParamBound, Token![+]>, items: &[CompatItem], used: &HashSet<String>) -> TokenStream {
+    let CompatContext { dyn_ident, ty_generics, .. } = ctx;
+    let span = dyn_ident.span();
+    let dyn_lt = Lifetime::new(&format!("'{}", unique_name("__dyn__", used)), span);
+    let ref_lt = Lifetime::new(&format!("'{}", unique_name("__ref__", used)), span);
+
+    let mut impl_generics = generics.clone();
+    impl_generics.params.insert(0, LifetimeDef::new(dyn_lt.clone()).into());
+    let mut args = generics.params.iter()
+        .map(|x| match x {
+            GenericParam::Type(x) => x.ident.to_token_stream(),
+            GenericParam::Lifetime(x) => x.lifetime.to_token_stream(),
+            GenericParam::Const(x) => x.ident.to_token_stream()
+        })
+        .collect::<Vec<_>>();
+    let mut types = Vec::new();
+    for (attrs, ident, bounds) in &state.assoc_types {
+        let param = format_ident!("{}", unique_name(&format!("__{ident}"), used), span = span);
+        impl_generics.params.push(TypeParam { colon_token: Some(Default::default()), bounds: bounds.clone(), ..TypeParam::from(param.clone()) }.into());
+        args.push(quote_spanned! { span => #ident = #param });
+        types.push(quote_spanned! { span => #(#attrs)* type #ident = #param; });
+    }
+    let args = match args.is_empty() {
+        true => None,
+        false => Some(quote_spanned! { span => <#(#args),*> })
+    };
+
+    let mut ref_generics = impl_generics.clone();
+    ref_generics.params.insert(0, LifetimeDef::new(ref_lt.clone()).into());
+    let (impl_generics, _, _) = impl_generics.split_for_impl();
+    let (ref_generics, _, _) = ref_generics.split_for_impl();
+    let methods = items.iter().map(|x| &x.pointer).collect::<Vec<_>>();
+
+    let mut result = TokenStream::new();
+    for markers in [quote!(), quote!(+ ::core::marker::Send), quote!(+ ::core::marker::Send + ::core::marker::Sync)] {
+        let object = quote_spanned! { span => dyn #dyn_ident #args #markers + #dyn_lt };
+        let mut pointers = vec![(None, &impl_generics, quote_spanned! { span => ::fast_async_trait::__private::Box<#object> })];
+        if !state.by_mut {
+            // `Arc` isn't available on targets without atomic pointers
+            pointers.push((Some(quote_spanned! { span => #[cfg(target_has_atomic = "ptr")] }), &impl_generics, quote_spanned! { span => ::fast_async_trait::__private::Arc<#object> }));
+            pointers.push((None, &ref_generics, quote_spanned! { span => &#ref_lt (#object) }));
+        }
+
+        for (cfg, impl_generics, pointer) in pointers {
+            let mut where_clause = generics.where_clause.clone();
+            if !supertraits.is_empty() {
+                where_clause.get_or_insert_with(|| WhereClause { where_token: Default::default(), predicates: Punctuated::new() }).predicates.push(parse_quote_spanned! { span => #pointer: #supertraits });
+            }
+
+            result.extend(quote_spanned! { span =>
+                #cfg
+                #[allow(deprecated)]
+                #unsafety impl #impl_generics #dyn_ident #ty_generics for #pointer #where_clause {
+                    #(#types)*
+                    #(#methods)*
+                }
+            });
+        }
+    }
+
+    return result
+}
+
+struct CompatContext<'a> {
+    trait_ident: &'a Ident,
+    dyn_ident: &'a Ident,
+    ty_generics: TokenStream,
+    this: &'a Ident,
+    assoc_types: &'a [Ident],
+    trait_type_params: Vec<Ident>,
+    future: TokenStream,
+}
+
+#[derive(Default)]
+struct CompatState {
+    /// Some method takes `self` by value, so `Compat` can only wrap sized types
+    sized: bool,
+    /// Some method's future doesn't borrow anything, so the futures of `Compat` have to be `'static`
+    borrows_nothing: bool,
+    /// Some item keeps `DynTrait` from being used as a trait object (or its pointers from forwarding to it), so they don't implement it
+    dyn_incompatible: bool,
+    /// Some method takes `&mut self`, so only `Box`es implement `DynTrait`
+    by_mut: bool,
+    /// Attributes, names and bounds of the associated types, which are bound by the trait objects
+    assoc_types: Vec<(Vec<Attribute>, Ident, Punctuated<TypeParamBound, Token![+]>)>,
+    errors: Vec<TokenStream>,
+}
+
+impl CompatContext<'_> {
+    /// Qualifies `Self::Item` as `<Self as Trait>::Item`, since the implementors of both traits see both associated types
+    fn qualify (&self, trait_ident: &Ident) -> SelfReplace<'_> {
+        return SelfReplace {
+            ident: format_ident!("Self"),
+            trait_ident: match trait_ident == self.trait_ident {
+                true => self.trait_ident,
+                false => self.dyn_ident
+            },
+            trait_generics: self.ty_generics.clone(),
+            assoc_types: self.assoc_types,
+        }
+    }
+}
+
+fn compat_item (ctx: &CompatContext, state: &mut CompatState, item: &AsyncTraitItem) -> CompatItem {
+    let CompatContext { trait_ident, dyn_ident, ty_generics, this, .. } = ctx;
+
+    return match item {
+        AsyncTraitItem::Type(TraitItemType { attrs, type_token, ident, generics, colon_token, bounds, semi_token, .. }) => {
+            let decl_attrs = mirrored_attrs(attrs, true);
+            let attrs = mirrored_attrs(attrs, false);
+            let (impl_generics, assoc_ty_generics, where_clause) = generics.split_for_impl();
+            match generics.params.is_empty() && generics.where_clause.is_none() && !used_idents(bounds.to_token_stream()).contains("Self") {
+                true => state.assoc_types.push((attrs.clone(), ident.clone(), bounds.clone())),
+                false => state.dyn_incompatible = true
+            }
+
+            CompatItem {
+                decl: quote! { #(#decl_attrs)* #type_token #ident #impl_generics #colon_token #bounds #where_clause #semi_token },
+                forward: quote! { #(#attrs)* #type_token #ident #impl_generics = <#this as #trait_ident #ty_generics>::#ident #assoc_ty_generics #where_clause #semi_token },
+                backward: quote! { #(#attrs)* #type_token #ident #impl_generics = <#this as #dyn_ident #ty_generics>::#ident #assoc_ty_generics #where_clause #semi_token },
+                pointer: TokenStream::new(),
+            }
+        },
+
+        AsyncTraitItem::Const(TraitItemConst { attrs, const_token, ident, colon_token, ty, semi_token, .. }) => {
+            let decl_attrs = mirrored_attrs(attrs, true);
+            let attrs = mirrored_attrs(attrs, false);
+            let mut forward_ty = ty.clone();
+            replace_self_ty(&mut forward_ty, &ctx.qualify(dyn_ident));
+            let mut backward_ty = ty.clone();
+            replace_self_ty(&mut backward_ty, &ctx.qualify(trait_ident));
+            state.dyn_incompatible = true;
+
+            CompatItem {
+                decl: quote! { #(#decl_attrs)* #const_token #ident #colon_token #ty #semi_token },
+                forward: quote! { #(#attrs)* #const_token #ident #colon_token #forward_ty = <#this as #trait_ident #ty_generics>::#ident #semi_token },
+                backward: quote! { #(#attrs)* #const_token #ident #colon_token #backward_ty = <#this as #dyn_ident #ty_generics>::#ident #semi_token },
+                pointer: TokenStream::new(),
+            }
+        },
+
+        AsyncTraitItem::Method(method) => compat_method(ctx, state, method),
+
+        AsyncTraitItem::Macro(mac) => {
+            state.dyn_incompatible = true;
+            state.errors.push(Error::new_spanned(mac, "macro invocations can't be mirrored by `compat`").into_compile_error());
+            CompatItem::default()
+        },
+
+        // already reported by the trait itself
+        AsyncTraitItem::Verbatim(_) => {
+            state.dyn_incompatible = true;
+            CompatItem::default()
+        }
+    }
+}
+
+fn compat_method (ctx: &CompatContext, state: &mut CompatState, method: &AsyncTraitItemMethod) -> CompatItem {
+    let CompatContext { trait_ident, dyn_ident, ty_generics, this, future, .. } = ctx;
+    let span = method.sig.span();
+    // the same as the trait's expansion, so that the lifetimes get the same names
+    let mut used = used_idents(method.to_token_stream());
+    used.insert(trait_ident.to_string());
+
+    let AsyncTraitItemMethod { attrs, sig, default, .. } = method;
+    let Signature { constness, asyncness, unsafety, abi, fn_token, ident, mut generics, mut inputs, variadic, output, .. } = sig.clone();
+    let decl_attrs = mirrored_attrs(attrs, true);
+    let attrs = mirrored_attrs(attrs, false);
+    let is_async = asyncness.is_some() || returns_future(&output);
+    let mut boxed_output = None;
+
+    let (output, backward_generics) = match is_async {
+        true => {
+            let (_, receiver_lt, output_lt) = future_generics(&mut inputs, &mut generics, &used);
+            let mut bounds = future_bounds(&asyncness, output, span);
+            // errors with the lifetimes are reported by the trait itself
+            if elide_output(bounds.iter_mut(), output_lt.as_ref()).is_err() || generics.lifetimes().count() > 1 {
+                state.dyn_incompatible = true;
+                return CompatItem::default()
+            }
+
+            let output = match future_output(&bounds) {
+                Some(output) if !contains_impl_trait(output) => output.clone(),
+                Some(output) => return compat_error(state, output, format!("method `{ident}` can't be mirrored by `compat`, since its output contains an `impl Trait`")),
+                None => return compat_error(state, &bounds, format!("method `{ident}` can't be mirrored by `compat`, since its future doesn't name its output"))
+            };
+
+            if is_boxed(&method.attrs) {
+                boxed_output = Some(output.clone());
+            }
+
+            let backward_generics = generics.clone();
+            // `Self: 'lt` is implied by the receiver, and would make the lifetime early bound, unlike in hand-written implementations
+            if let (Some(lt), Some(where_clause)) = (&receiver_lt, &mut generics.where_clause) {
+                let implied = quote!(Self: #lt).to_string();
+                where_clause.predicates = core::mem::take(&mut where_clause.predicates).into_iter()
+                    .filter(|x| x.to_token_stream().to_string() != implied)
+                    .collect();
+            }
+
+            let lifetime = match generics.lifetimes().next() {
+                Some(LifetimeDef { lifetime, .. }) => lifetime.clone(),
+                None => {
+                    // the boxed future borrows nothing, but it still can't outlive the types it captures
+                    let lifetime = Lifetime::new(&format!("'{}", unique_name("__boxed__", &used)), span);
+                    let type_params = generics.type_params().map(|x| x.ident.clone()).collect::<Vec<_>>();
+                    generics.params.insert(0, LifetimeDef::new(lifetime.clone()).into());
+
+                    let predicates = &mut generics.make_where_clause().predicates;
+                    predicates.push(parse_quote_spanned! { span => Self: #lifetime });
+                    for param in type_params.iter().chain(&ctx.trait_type_params) {
+                        predicates.push(parse_quote_spanned! { span => #param: #lifetime });
+                    }
+
+                    if default.is_none() {
+                        state.borrows_nothing = true;
+                        if let Some(param) = type_params.first() {
+                            state.errors.push(Error::new(param.span(), format!(
+                                "method `{ident}` can't be implemented for `Compat` by `compat`, since its future doesn't borrow anything, but it's generic over types that may not be `'static`"
+                            )).into_compile_error());
+                        }
+                    }
+                    lifetime
+                }
+            };
+
+            (ReturnType::Type(Default::default(), Box::new(parse_quote_spanned! { span => #future<#lifetime, #output> })), Some(backward_generics))
+        },
+        false => (output, None)
+    };
+
+    let args = normalize_args(inputs.iter_mut());
+    let receiver = receiver_kind(&inputs);
+    if receiver == "by_value" {
+        // keeps the trait object safe
+        generics.make_where_clause().predicates.push(parse_quote_spanned! { span => Self: ::core::marker::Sized });
+    }
+    let self_arg = match receiver {
+        "none" => None,
+        _ => Some(quote_spanned! { span => self, })
+    };
+    // trait objects can only forward methods taking `self` by reference
+    let pointer_self = match receiver {
+        "by_ref" => Some(quote_spanned! { span => &**self, }),
+        "by_mut" => {
+            state.by_mut = true;
+            Some(quote_spanned! { span => &mut **self, })
+        },
+        _ => None
+    };
+    if pointer_self.is_none() || generics.type_params().next().is_some() || generics.const_params().next().is_some() || requires_sized(&sig.generics) {
+        state.dyn_incompatible = true;
+    }
+
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let turbofish = turbofish(&generics);
+    let call_unsafety = unsafety.map(|_| quote_spanned! { span => unsafe });
+
+    let decl = quote_spanned! { span =>
+        #(#decl_attrs)*
+        #constness #unsafety #abi #fn_token #ident #impl_generics (#inputs #variadic) #output #where_clause;
+    };
+
+    let (forward, pointer) = {
+        let replace = ctx.qualify(dyn_ident);
+        let (inputs, output, generics) = qualify_signature(&inputs, &output, &generics, &replace);
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let wrap_unsafe = |call: TokenStream| match call_unsafety {
+            Some(_) => quote_spanned! { span => unsafe { #call } },
+            None => call
+        };
+
+        let call = wrap_unsafe(quote_spanned! { span => <Self as #trait_ident #ty_generics>::#ident #turbofish (#self_arg #(#args),*) });
+        // boxed futures are only coerced
+        let body = match (is_async, &boxed_output) {
+            (true, None) => quote_spanned! { span => ::fast_async_trait::__private::Box::pin(#call) },
+            _ => call
+        };
+        let pointer_call = wrap_unsafe(quote_spanned! { span =>
+            <<Self as ::core::ops::Deref>::Target as #dyn_ident #ty_generics>::#ident #turbofish (#pointer_self #(#args),*)
+        });
+
+        let sig = quote_spanned! { span =>
+            #(#attrs)*
+            #[inline]
+            #constness #unsafety #abi #fn_token #ident #impl_generics (#inputs #variadic) #output #where_clause
+        };
+        (quote_spanned! { span => #sig { return #body } }, quote_spanned! { span => #sig { return #pointer_call } })
+    };
+
+    // default async methods return their own opaque type, which can't be replaced
+    if is_async && default.is_some() {
+        return CompatItem { decl, forward, backward: TokenStream::new(), pointer }
+    }
+
+    let self_arg = match receiver {
+        "by_ref" => Some(quote_spanned! { span => &self.0, }),
+        "by_mut" => Some(quote_spanned! { span => &mut self.0, }),
+        "by_value" => {
+            state.sized = true;
+            Some(quote_spanned! { span => self.0, })
+        },
+        "none" => None,
+        _ => return CompatItem {
+            decl,
+            forward,
+            backward: Error::new_spanned(inputs.first(), format!("method `{ident}` can't be implemented for `Compat` by `compat`, since it has a custom receiver")).into_compile_error(),
+            pointer
+        }
+    };
+
+    let backward = {
+        let replace = ctx.qualify(trait_ident);
+        let call = quote_spanned! { span => <#this as #dyn_ident #ty_generics>::#ident #turbofish (#self_arg #(#args),*) };
+        let call = match call_unsafety {
+            Some(_) => quote_spanned! { span => unsafe { #call } },
+            None => call
+        };
+
+        match (backward_generics, output) {
+            (Some(generics), _) if boxed_output.is_some() => {
+                let (inputs, output, generics) = qualify_signature(&inputs, &ReturnType::Type(Default::default(), Box::new(boxed_output.unwrap())), &generics, &replace);
+                let output = match output {
+                    ReturnType::Type(_, ty) => ty,
+                    ReturnType::Default => unreachable!()
+                };
+                let future = trait_future(&(*trait_ident).clone().into(), &ident, generics.lifetimes().next().map(|x| &x.lifetime), &output);
+                let (impl_generics, _, where_clause) = generics.split_for_impl();
+                quote_spanned! { span =>
+                    #(#attrs)*
+                    #[inline]
+                    #constness #unsafety #abi #fn_token #ident #impl_generics (#inputs #variadic) -> #future #where_clause {
+                        return #call
+                    }
+                }
+            },
+
+            (Some(generics), ReturnType::Type(_, ty)) => {
+                let future_name = format_ident!("{}", to_pascal_case(&ident.to_string()), span = ident.span());
+                let (_, future, generics) = qualify_signature(&inputs, &ReturnType::Type(Default::default(), ty), &generics, &replace);
+                let future = match (future, generics.lifetimes().next()) {
+                    (ReturnType::Type(_, ty), Some(_)) => ty.into_token_stream(),
+                    // see `borrows_nothing`
+                    (ReturnType::Type(_, ty), None) => {
+                        let mut ty = *ty;
+                        replace_first_lifetime(&mut ty, Lifetime::new("'static", span));
+                        ty.into_token_stream()
+                    },
+                    (ReturnType::Default, _) => unreachable!()
+                };
+
+                let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+                let (inputs, _, _) = qualify_signature(&inputs, &ReturnType::Default, &generics, &replace);
+                quote_spanned! { span =>
+                    #(#attrs)*
+                    type #future_name #impl_generics = #future #where_clause;
+
+                    #(#attrs)*
+                    #[inline]
+                    #constness #unsafety #abi #fn_token #ident #impl_generics (#inputs #variadic) -> Self::#future_name #ty_generics #where_clause {
+                        return #call
+                    }
+                }
+            },
+
+            (_, output) => {
+                let (inputs, output, generics) = qualify_signature(&inputs, &output, &generics, &replace);
+                let (impl_generics, _, where_clause) = generics.split_for_impl();
+                quote_spanned! { span =>
+                    #(#attrs)*
+                    #[inline]
+                    #constness #unsafety #abi #fn_token #ident #impl_generics (#inputs #variadic) #output #where_clause {
+                        return #call
+                    }
+                }
+            }
+        }
+    };
+
+    return CompatItem { decl, forward, backward, pointer }
+}
+
+/// Checks whether a method requires `Self: Sized`, which keeps it from being called on trait objects
+fn requires_sized (generics: &Generics) -> bool {
+    return generics.where_clause.iter().flat_map(|x| &x.predicates).any(|x| match x {
+        WherePredicate::Type(PredicateType { bounded_ty: Type::Path(TypePath { qself: None, path }), bounds, .. }) if path.is_ident("Self") => bounds.iter().any(|x| matches!(
+            x,
+            TypeParamBound::Trait(TraitBound { modifier: TraitBoundModifier::None, path, .. }) if path.segments.last().is_some_and(|x| x.ident == "Sized")
+        )),
+        _ => false
+    })
+}
+
+#[inline]
+fn compat_error (state: &mut CompatState, tokens: impl ToTokens, message: String) -> CompatItem {
+    state.dyn_incompatible = true;
+    state.errors.push(Error::new_spanned(tokens, message).into_compile_error());
+    return CompatItem::default()
+}
+
+/// Checks whether `ty` contains an `impl Trait` type
+pub fn contains_impl_trait (ty: &Type) -> bool {
+    let mut found = false;
+    replace_impl_traits(&mut ty.clone(), &mut |x| {
+        found = true;
+        Type::ImplTrait(x)
+    });
+    return found
+}
+
+/// Replaces the lifetime of a boxed future (its first generic argument)
+fn replace_first_lifetime (ty: &mut Type, lifetime: Lifetime) {
+    if let Type::Path(TypePath { path, .. }) = ty {
+        if let Some(PathSegment { arguments: PathArguments::AngleBracketed(AngleBracketedGenericArguments { args, .. }), .. }) = path.segments.last_mut() {
+            if let Some(GenericArgument::Lifetime(lt)) = args.first_mut() {
+                *lt = lifetime;
+            }
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::{path::PathBuf, collections::BTreeMap, sync::Mutex};
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{*, parse::{Parse, Parser}};
+
+/// Environment variable that enables debug mode for every annotated trait and impl
+const DEBUG_ENV: &str = "FAST_ASYNC_TRAIT_DEBUG";
+/// Attribute put on generated items in the dump, replaced by a comment once pretty-printed
+const ORIGIN_ATTR: &str = "__fast_async_trait_generated_from";
+
+/// Number of expansions dumped so far under every file name, so that items with the same name (i.e. traits in different modules) don't overwrite each other
+static DUMPED: Mutex<BTreeMap<String, usize>> = Mutex::new(BTreeMap::new());
+
+/// Returns whether the expansion should be dumped, either because of the `debug` option or the environment variable
+#[inline]
+pub fn debug_enabled (debug: bool) -> bool {
+    return debug || std::env::var_os(DEBUG_ENV).is_some_and(|x| !x.is_empty() && x != "0")
+}
+
+/// Marks every item in `tokens` as generated from the method `origin`. Falls back to the tokens untouched if they can't be parsed.
+pub fn annotate<T: Parse + ToTokens> (tokens: &TokenStream, origin: &Ident, attrs: fn(&mut T) -> Option<&mut Vec<Attribute>>) -> TokenStream {
+    let attr_ident = Ident::new(ORIGIN_ATTR, origin.span());
+    let origin = origin.to_string();
+
+    let parser = |input: parse::ParseStream| {
+        let mut items = Vec::new();
+        while !input.is_empty() {
+            items.push(input.parse::<T>()?);
+        }
+        Ok(items)
+    };
+
+    return match parser.parse2(tokens.clone()) {
+        Ok(mut items) => {
+            for item in items.iter_mut() {
+                if let Some(attrs) = attrs(item) {
+                    attrs.insert(0, parse_quote!(#[#attr_ident = #origin]));
+                }
+            }
+            quote! { #(#items)* }
+        },
+        Err(_) => tokens.clone()
+    }
+}
+
+pub fn trait_item_attrs (item: &mut TraitItem) -> Option<&mut Vec<Attribute>> {
+    return match item {
+        TraitItem::Const(x) => Some(&mut x.attrs),
+        TraitItem::Method(x) => Some(&mut x.attrs),
+        TraitItem::Type(x) => Some(&mut x.attrs),
+        TraitItem::Macro(x) => Some(&mut x.attrs),
+        _ => None
+    }
+}
+
+pub fn impl_item_attrs (item: &mut ImplItem) -> Option<&mut Vec<Attribute>> {
+    return match item {
+        ImplItem::Const(x) => Some(&mut x.attrs),
+        ImplItem::Method(x) => Some(&mut x.attrs),
+        ImplItem::Type(x) => Some(&mut x.attrs),
+        ImplItem::Macro(x) => Some(&mut x.attrs),
+        _ => None
+    }
+}
+
+pub fn item_attrs (item: &mut Item) -> Option<&mut Vec<Attribute>> {
+    return match item {
+        Item::Type(x) => Some(&mut x.attrs),
+        Item::Macro(x) => Some(&mut x.attrs),
+        Item::Use(x) => Some(&mut x.attrs),
+        _ => None
+    }
+}
+
+/// Pretty-prints the expansion of `name`, writing it to `$OUT_DIR/fast_async_trait/{name}.rs` when the crate has a build script
+/// (`{name}_2.rs`, `{name}_3.rs`, etc. for the following expansions with the same name), and to the standard error otherwise.
+pub fn dump (name: &str, tokens: &TokenStream) {
+    let pretty = match parse2::<File>(tokens.clone()) {
+        Ok(file) => prettyplease::unparse(&file),
+        Err(_) => tokens.to_string()
+    };
+
+    let pretty = pretty.lines().map(|line| {
+        let trimmed = line.trim_start();
+        match trimmed.strip_prefix(&format!("#[{ORIGIN_ATTR} = \"")).and_then(|x| x.strip_suffix("\"]")) {
+            Some(origin) => format!("{}// generated from method `{origin}`\n", &line[..line.len() - trimmed.len()]),
+            None => format!("{line}\n")
+        }
+    }).collect::<String>();
+
+    let file_name = name.chars()
+        .map(|c| match c.is_alphanumeric() {
+            true => c,
+            false => '_'
+        })
+        .collect::<String>();
+    let file_name = {
+        let mut dumped = DUMPED.lock().unwrap_or_else(|x| x.into_inner());
+        let count = dumped.entry(file_name.clone()).or_default();
+        *count += 1;
+        match *count {
+            1 => file_name,
+            count => format!("{file_name}_{count}")
+        }
+    };
+
+    if let Some(out_dir) = std::env::var_os("OUT_DIR") {
+        let path = PathBuf::from(out_dir).join("fast_async_trait").join(format!("{file_name}.rs"));
+        let written = std::fs::create_dir_all(path.parent().unwrap()).and_then(|_| std::fs::write(&path, &pretty));
+
+        if written.is_ok() {
+            eprintln!("fast_async_trait: expansion of `{name}` written to {}", path.display());
+            return
+        }
+    }
+
+    eprintln!("fast_async_trait: expansion of `{name}`\n{pretty}");
+}
@@ -2,7 +2,9 @@ use derive_syn_parse::Parse;
 use proc_macro2::TokenStream;
 use quote::{ToTokens, TokenStreamExt};
 use syn::{*, punctuated::{Punctuated}, parse::{Parse, ParseStream}};
+use crate::recover::parse_items;
 
+/// Trait method, which unlike [`TraitItemMethod`] may be async
 #[derive(Parse)]
 pub struct AsyncTraitItemMethod {
     #[call(Attribute::parse_outer)]
@@ -13,16 +15,18 @@ pub struct AsyncTraitItemMethod {
     pub semi_token: Option<Token![;]>,
 }
 
+/// Item of an `#[async_trait_def]` trait
 #[non_exhaustive]
 pub enum AsyncTraitItem {
     Const(TraitItemConst),
     Method(AsyncTraitItemMethod),
     Type(TraitItemType),
     Macro(TraitItemMacro),
-    #[allow(unused)]
+    /// Item that failed to parse, replaced by its error
     Verbatim(TokenStream),
 }
 
+/// Trait annotated with `#[async_trait_def]`
 pub struct AsyncTraitDef {
     pub attrs: Vec<Attribute>,
     pub vis: Visibility,
@@ -37,6 +41,9 @@ pub struct AsyncTraitDef {
     pub items: Vec<AsyncTraitItem>,
 }
 
+/// Trait items given to `async_trait_def_items!`
+pub struct AsyncTraitItems(pub Vec<AsyncTraitItem>);
+
 impl ToTokens for AsyncTraitItemMethod {
     #[inline]
     fn to_tokens(&self, tokens: &mut TokenStream) {
@@ -87,6 +94,7 @@ impl Parse for AsyncTraitDef {
             generics,
         );
 
+        #[allow(clippy::too_many_arguments)]
         fn parse_rest_of_trait(
             input: parse::ParseStream,
             mut attrs: Vec<Attribute>,
@@ -118,10 +126,7 @@ impl Parse for AsyncTraitDef {
             let content;
             let brace_token = braced!(content in input);
             attrs.append(&mut Attribute::parse_inner(&content)?);
-            let mut items = Vec::new();
-            while !content.is_empty() {
-                items.push(content.parse()?);
-            }
+            let items = parse_items(&content, |e| AsyncTraitItem::Verbatim(e.into_compile_error()));
     
             Ok(AsyncTraitDef {
                 attrs,
@@ -140,6 +145,13 @@ impl Parse for AsyncTraitDef {
     }
 }
 
+impl Parse for AsyncTraitItems {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let items = parse_items(input, |e| AsyncTraitItem::Verbatim(e.into_compile_error()));
+        Ok(AsyncTraitItems(items))
+    }
+}
+
 impl Parse for AsyncTraitItem {
     fn parse(input: ParseStream) -> Result<Self> {
         //TraitItem
@@ -181,8 +193,8 @@ impl Parse for AsyncTraitItem {
 
         match (vis, defaultness) {
             (Visibility::Inherited, None) => {}
-            _ => todo!()
-            //_ => return Ok(AsyncTraitItem::Verbatim(verbatim_between(begin, input))),
+            (Visibility::Inherited, Some(defaultness)) => return Err(Error::new(defaultness.span, "`default` isn't permitted on trait items")),
+            (vis, _) => return Err(Error::new_spanned(vis, "visibility qualifiers aren't permitted on trait items")),
         }
 
         let item_attrs = match &mut item {
@@ -191,9 +203,6 @@ impl Parse for AsyncTraitItem {
             AsyncTraitItem::Type(item) => &mut item.attrs,
             AsyncTraitItem::Macro(item) => &mut item.attrs,
             AsyncTraitItem::Verbatim(_) => unreachable!(),
-
-            #[cfg(syn_no_non_exhaustive)]
-            _ => unreachable!(),
         };
         attrs.append(item_attrs);
         *item_attrs = attrs;
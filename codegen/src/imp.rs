@@ -1,5 +1,7 @@
 use syn::{*, parse::{Parse, ParseStream}};
+use crate::recover::parse_items;
 
+/// Implementation annotated with `#[async_trait_impl]`
 pub struct AsyncItemImpl {
     pub attrs: Vec<Attribute>,
     pub defaultness: Option<Token![default]>,
@@ -12,10 +14,21 @@ pub struct AsyncItemImpl {
     pub items: Vec<ImplItem>,
 }
 
+/// Impl items given to `async_trait_impl_items!`
+pub struct AsyncImplItems(pub Vec<ImplItem>);
+
+impl Parse for AsyncImplItems {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let items = parse_items(input, |e| ImplItem::Verbatim(e.into_compile_error()));
+        Ok(AsyncImplItems(items))
+    }
+}
+
 impl Parse for AsyncItemImpl {
     fn parse(input: ParseStream) -> Result<Self> {
         let allow_verbatim_impl = false;
-        parse_impl(input, allow_verbatim_impl).map(Option::unwrap)
+        let span = input.span();
+        parse_impl(input, allow_verbatim_impl)?.ok_or_else(|| Error::new(span, "expected a trait or inherent implementation"))
     }
 }
 
@@ -48,15 +61,12 @@ fn parse_impl(input: ParseStream, allow_verbatim_impl: bool) -> Result<Option<As
         input.parse::<Token![const]>()?;
     }
 
-    //let begin = input.fork();
     let polarity = if input.peek(Token![!]) && !input.peek2(token::Brace) {
         Some(input.parse::<Token![!]>()?)
     } else {
         None
     };
 
-    #[cfg(not(feature = "printing"))]
-    let first_ty_span = input.span();
     let mut first_ty: Type = input.parse()?;
     let self_ty: Type;
     let trait_;
@@ -78,21 +88,16 @@ fn parse_impl(input: ParseStream, allow_verbatim_impl: bool) -> Result<Option<As
                 unreachable!();
             }
         } else if !allow_verbatim_impl {
-            #[cfg(feature = "printing")]
             return Err(Error::new_spanned(first_ty_ref, "expected trait path"));
-            #[cfg(not(feature = "printing"))]
-            return Err(Error::new(first_ty_span, "expected trait path"));
         } else {
             trait_ = None;
         }
         self_ty = input.parse()?;
     } else {
         trait_ = None;
-        self_ty = if polarity.is_none() {
-            first_ty
-        } else {
-            todo!()
-            //Type::Verbatim(verbatim::between(begin, input))
+        self_ty = match polarity {
+            None => first_ty,
+            Some(bang) => return Err(Error::new(bang.span, "inherent implementations can't be negative"))
         };
     }
 
@@ -102,10 +107,7 @@ fn parse_impl(input: ParseStream, allow_verbatim_impl: bool) -> Result<Option<As
     let brace_token = braced!(content in input);
     attrs.append(&mut Attribute::parse_inner(&content)?);
 
-    let mut items = Vec::new();
-    while !content.is_empty() {
-        items.push(content.parse()?);
-    }
+    let items = parse_items(&content, |e| ImplItem::Verbatim(e.into_compile_error()));
 
     if has_visibility || is_const_impl || is_impl_for && trait_.is_none() {
         Ok(None)
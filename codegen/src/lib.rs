@@ -0,0 +1,1231 @@
+//! Parsing and code generation behind [`fast_async_trait`](https://docs.rs/fast_async_trait), operating on [`proc_macro2::TokenStream`]s,
+//! so that it can be used from other procedural macros (or build scripts) and tested outside of a compiler invocation.
+//!
+//! The entry points ([`async_trait_def`], [`async_trait_impl`], [`async_trait_def_items`] and [`async_trait_impl_items`]) take the same input as
+//! the macros of the same name, and return their expansion. Errors are reported as `compile_error!` invocations inside the expansion.
+
+use std::collections::HashSet;
+use proc_macro2::{TokenStream, TokenTree, Span};
+use syn::{*, punctuated::Punctuated, spanned::Spanned};
+use quote::{quote, quote_spanned, format_ident, ToTokens};
+
+mod def;
+pub use def::*;
+
+mod imp;
+pub use imp::*;
+
+mod args;
+pub use args::{DefArgs, CompatArgs, ImplArgs};
+
+mod marker;
+use marker::*;
+
+mod compat;
+use compat::*;
+
+mod native;
+use native::*;
+
+mod spawn;
+use spawn::*;
+
+mod boxed;
+use boxed::*;
+
+mod size;
+use size::*;
+
+mod recover;
+
+mod debug;
+use debug::*;
+
+pub mod build;
+
+/// Expands a trait annotated with `#[async_trait_def(attrs)]`
+pub fn async_trait_def (attrs: TokenStream, items: TokenStream) -> TokenStream {
+    let (DefArgs { compat, native, static_futures, debug }, args_error) = parse_args(attrs);
+    let source = items.to_string();
+    let AsyncTraitDef { attrs, vis, unsafety, auto_token, trait_token, ident, generics, colon_token, supertraits, mut items, .. } = match parse2(items.clone()) {
+        Ok(x) => x,
+        Err(e) => return recover_input(e, items)
+    };
+    let (impl_generics, _, where_generics) = generics.split_for_impl();
+
+    let assoc_types = items.iter()
+        .filter_map(|x| match x {
+            AsyncTraitItem::Type(TraitItemType { ident, .. }) => Some(ident.clone()),
+            _ => None
+        })
+        .collect::<Vec<_>>();
+
+    let mut extra_bounds = match &compat {
+        Some(args) => compat_bounds(args),
+        None => Vec::new()
+    };
+    if !extra_bounds.is_empty() {
+        items.iter_mut().for_each(require_send);
+    }
+    if static_futures {
+        extra_bounds.push(parse_quote! { 'static });
+    }
+
+    let marker = define_marker(&vis, &ident, &extra_bounds, &items, &source);
+    let impl_required = match auto_token {
+        Some(_) => None,
+        None => Some(define_impl_required())
+    };
+    let compat = compat.map(|args| define_compat(&args, CompatTrait {
+        attrs: &attrs,
+        vis: &vis,
+        unsafety,
+        ident: &ident,
+        generics: &generics,
+        supertraits: &supertraits,
+        items: &items,
+        impl_required: impl_required.is_some(),
+    }));
+    let spawn = define_spawn_ext(&attrs, &vis, &ident, &generics, &items, static_futures);
+    let native = native.map(|native| define_native_blanket(&native, NativeTrait {
+        unsafety,
+        ident: &ident,
+        generics: &generics,
+        supertraits: &supertraits,
+        items: &items,
+        impl_required: impl_required.is_some(),
+    }));
+
+    let items = items.into_iter()
+        .map(|x| {
+            let origin = match &x {
+                AsyncTraitItem::Method(method) if method.sig.asyncness.is_some() || returns_future(&method.sig.output) => Some(method.sig.ident.clone()),
+                _ => None
+            };
+            let (item, extra) = define_fn(&vis, &ident, &generics, &assoc_types, &extra_bounds, x);
+            (origin, item, extra)
+        })
+        .collect::<Vec<_>>();
+
+    let expand = |annotated: bool| {
+        let (items, extra) = items.iter()
+            .map(|(origin, item, extra)| match (annotated, origin) {
+                (true, Some(origin)) => (annotate(item, origin, trait_item_attrs), extra.as_ref().map(|x| annotate(x, origin, item_attrs))),
+                _ => (item.clone(), extra.clone())
+            })
+            .unzip::<_, _, Vec<_>, Vec<_>>();
+
+        let extra = extra.into_iter()
+            .flatten()
+            .collect::<TokenStream>();
+
+        quote! {
+            #(#attrs)*
+            #vis #unsafety #auto_token #trait_token #ident #impl_generics #colon_token #supertraits #where_generics {
+                #(#items)*
+                #impl_required
+            }
+
+            #extra
+            #marker
+            #compat
+            #native
+            #spawn
+            ::fast_async_trait::__check_features! {}
+        }
+    };
+
+    if debug_enabled(debug) {
+        dump(&ident.to_string(), &expand(true));
+    }
+
+    let expanded = expand(false);
+    return quote! {
+        #expanded
+        #args_error
+    }
+}
+
+/// Expands an implementation annotated with `#[async_trait_impl(attrs)]`
+pub fn async_trait_impl (attrs: TokenStream, items: TokenStream) -> TokenStream {
+    let (ImplArgs { unchecked, native, max_future_size, debug }, args_error) = parse_args(attrs);
+    let AsyncItemImpl { attrs, defaultness, unsafety, impl_token, generics, trait_, self_ty, mut items, .. } = match parse2(items.clone()) {
+        Ok(x) => x,
+        Err(e) => return recover_input(e, items)
+    };
+
+    let name = match &trait_ {
+        Some((_, path, _)) => format!("impl {} for {}", path.to_token_stream(), self_ty.to_token_stream()),
+        None => format!("impl {}", self_ty.to_token_stream())
+    };
+    let sizes = match native {
+        true => None,
+        false => {
+            let trait_path = match &trait_ {
+                Some((None, path, _)) => Some(path),
+                _ => None
+            };
+            Some(check_future_sizes(&name, &generics, trait_path, &self_ty, &mut items, max_future_size))
+        }
+    };
+
+    let (impl_required, checks, marker) = match &trait_ {
+        Some((None, path, _)) if !unchecked && !native => (Some(impl_required()), Some(check_marker(path, &items)), Some(marker_path(path))),
+        _ => (None, None, None)
+    };
+
+    let items = items.into_iter()
+        .map(|x| {
+            let origin = match &x {
+                ImplItem::Method(method) if !native && (method.sig.asyncness.is_some() || returns_future(&method.sig.output)) => Some(method.sig.ident.clone()),
+                _ => None
+            };
+            // native async methods already are what the trait expects
+            match (native, x) {
+                (true, mut x) => {
+                    let errors = take_native_attrs(&mut x);
+                    (origin, quote! { #x #errors })
+                },
+                (false, ImplItem::Method(method)) if origin.is_some() && is_boxed(&method.attrs) => (origin, impl_boxed_fn(method, marker.as_ref())),
+                (false, x) => (origin, impl_fn(x))
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let trait_ = trait_.map(|(x, y, z)| quote!(#x #y #z));
+
+    let expand = |annotated: bool| {
+        let items = items.iter().map(|(origin, item)| match (annotated, origin) {
+            (true, Some(origin)) => annotate(item, origin, impl_item_attrs),
+            _ => item.clone()
+        });
+
+        quote! {
+            #(#attrs)*
+            #defaultness #unsafety #impl_token #generics #trait_ #self_ty {
+                #(#items)*
+                #impl_required
+            }
+
+            #checks
+            #sizes
+            ::fast_async_trait::__check_features! {}
+        }
+    };
+
+    if debug_enabled(debug) {
+        dump(&name, &expand(true));
+    }
+
+    let expanded = expand(false);
+    return quote! {
+        #expanded
+        #args_error
+    }
+}
+
+/// Expands trait items produced by other macros (i.e. `macro_rules!`) as if they were written inside an `#[async_trait_def]` trait.
+/// Since free type aliases can't be declared from inside a trait, async methods with a default implementation aren't supported.
+pub fn async_trait_def_items (items: TokenStream) -> TokenStream {
+    let AsyncTraitItems(items) = match parse2(items) {
+        Ok(x) => x,
+        Err(e) => return e.into_compile_error()
+    };
+    let items = items.into_iter().map(|item| match item {
+        AsyncTraitItem::Method(AsyncTraitItemMethod { sig, default: Some(default), .. }) if sig.asyncness.is_some() || returns_future(&sig.output) => {
+            syn::Error::new(default.span(), "async methods with a default implementation aren't supported inside `async_trait_def_items!`").into_compile_error()
+        },
+        // the visibility and trait identifier are only used by default implementations
+        other => define_fn(&Visibility::Inherited, &format_ident!("Self"), &Generics::default(), &[], &[], other).0
+    });
+
+    return quote! {
+        #(#items)*
+    }
+}
+
+/// Expands impl items produced by other macros (i.e. `macro_rules!`) as if they were written inside an `#[async_trait_impl]` impl.
+pub fn async_trait_impl_items (items: TokenStream) -> TokenStream {
+    let AsyncImplItems(items) = match parse2(items) {
+        Ok(x) => x,
+        Err(e) => return e.into_compile_error()
+    };
+    let items = items.into_iter().map(impl_fn);
+
+    return quote! {
+        #(#items)*
+    }
+}
+
+/// Expands `async_trait! { (options) trait ... }`, the function-like counterpart of `#[async_trait_def(options)]`, for traits generated by other macros.
+/// The options are optional.
+pub fn async_trait (tokens: TokenStream) -> TokenStream {
+    let (args, item) = split_args(tokens);
+    return async_trait_def(args, item)
+}
+
+/// Expands `async_impl! { (options) impl ... }`, the function-like counterpart of `#[async_trait_impl(options)]`, for implementations generated by other macros.
+/// The options are optional.
+pub fn async_impl (tokens: TokenStream) -> TokenStream {
+    let (args, item) = split_args(tokens);
+    return async_trait_impl(args, item)
+}
+
+/// Splits the leading parenthesized options of a function-like macro from its item
+fn split_args (tokens: TokenStream) -> (TokenStream, TokenStream) {
+    let mut tokens = tokens.into_iter().peekable();
+    return match tokens.peek() {
+        Some(TokenTree::Group(group)) if group.delimiter() == proc_macro2::Delimiter::Parenthesis => {
+            let args = group.stream();
+            tokens.next();
+            (args, tokens.collect())
+        },
+        _ => (TokenStream::new(), tokens.collect())
+    }
+}
+
+/// Parses the macro's options, falling back to the defaults (and the error) if they're invalid, so that the item is still expanded
+fn parse_args<T: syn::parse::Parse + Default> (attrs: TokenStream) -> (T, Option<TokenStream>) {
+    return match parse2(attrs) {
+        Ok(args) => (args, None),
+        Err(e) => (T::default(), Some(e.into_compile_error()))
+    }
+}
+
+/// Emits the item untouched alongside the error when it can't be parsed at all, so that it doesn't disappear for its users
+fn recover_input (e: syn::Error, items: TokenStream) -> TokenStream {
+    let error = e.into_compile_error();
+
+    return quote! {
+        #error
+        #items
+    }
+}
+
+/// Expands a single trait item. Async methods (and methods returning `impl Future`) become an associated future type and a method returning it
+/// (or just a method returning a boxed future, if annotated with `#[boxed]`), while any other item is returned untouched.
+///
+/// `vis`, `trait_ident` and `trait_generics` are those of the trait, and `assoc_types` the associated types it declares. They're only used by default implementations,
+/// whose futures are declared as free type aliases, returned as the second element of the tuple. `extra_bounds` are added to the bounds of every future (i.e. `Send`).
+#[inline]
+pub fn define_fn (vis: &Visibility, trait_ident: &Ident, trait_generics: &Generics, assoc_types: &[Ident], extra_bounds: &[TypeParamBound], sig: AsyncTraitItem) -> (TokenStream, Option<TokenStream>) {
+    return match sig {
+        AsyncTraitItem::Method(method) if (method.sig.asyncness.is_some() || returns_future(&method.sig.output)) && is_boxed(&method.attrs) => (define_boxed_fn(extra_bounds, method), None),
+        AsyncTraitItem::Method(method) if method.sig.asyncness.is_some() || returns_future(&method.sig.output) => define_async_fn(vis, trait_ident, trait_generics, assoc_types, extra_bounds, method),
+        other => (other.to_token_stream(), None)
+    }
+}
+
+fn define_async_fn (vis: &Visibility, trait_ident: &Ident, trait_generics: &Generics, assoc_types: &[Ident], extra_bounds: &[TypeParamBound], method: AsyncTraitItemMethod) -> (TokenStream, Option<TokenStream>) {
+    let span = method.sig.span();
+    let mut used = used_idents(quote! { #trait_generics #method });
+    used.insert(trait_ident.to_string());
+    let AsyncTraitItemMethod { mut attrs, sig: Signature { constness, asyncness, unsafety, abi, fn_token, ident, mut generics, mut inputs, variadic, output, .. }, default, semi_token } = method;
+    // deprecating the associated types would warn on every implementation, since they have to name them
+    let mirrored = mirrored_attrs(&attrs, false);
+
+    let mut extra_bounds = extra_bounds.to_vec();
+    if take_attr(&mut attrs, "detached") {
+        extra_bounds.push(parse_quote_spanned! { span => 'static });
+    }
+    let detached = extra_bounds.iter().any(|x| matches!(x, TypeParamBound::Lifetime(lt) if lt.ident == "static"));
+
+    let future_name = format_ident!("{}", to_pascal_case(&ident.to_string()), span = ident.span());
+    let mut future_bounds = future_bounds(&asyncness, output, span);
+    for bound in &extra_bounds {
+        let bound_str = bound.to_token_stream().to_string();
+        if !future_bounds.iter().any(|x| x.to_token_stream().to_string() == bound_str) {
+            future_bounds.push(bound.clone());
+        }
+    }
+    
+    let (life, _, output_lt) = future_generics(&mut inputs, &mut generics, &used);
+    let bindings = normalize_inputs(inputs.iter_mut());
+    if let Err(e) = elide_output(future_bounds.iter_mut(), output_lt.as_ref()) {
+        return (e.into_compile_error(), None);
+    }
+    if generics.lifetimes().count() > 1 {
+        return (syn::Error::new(generics.lifetimes().nth(1).unwrap().span(), "Currently only one lifetime per future is supported").into_compile_error(), None);
+    };
+    if detached {
+        if let Err(e) = require_static(&ident, &mut generics) {
+            return (e.into_compile_error(), None);
+        }
+    }
+    
+    let add_token = match life.is_empty() {
+        true => None,
+        false => Some(<Token![+]>::default())
+    };
+    let (impl_generics, ty_generics, where_generics) = generics.split_for_impl();
+
+    let (future_default, return_type, extra, defines) = match default {
+        Some(block) => {
+            let ty_ident = format_ident!("{trait_ident}{future_name}Default", span = ident.span());
+
+            // only methods taking `self` by value require it to be sized
+            let ty_sized = match receiver_kind(&inputs) {
+                "by_value" => None,
+                _ => Some(quote_spanned! { span => ?::core::marker::Sized + })
+            };
+
+            let ty_lt = generics.lifetimes().next().map(|LifetimeDef { lifetime, .. }| quote_spanned! { span => #lifetime + });
+
+            let alias_mirrored = mirrored_attrs(&attrs, true);
+            if quote!(#(#alias_mirrored)*).to_string() != quote!(#(#mirrored)*).to_string() {
+                attrs.push(parse_quote_spanned! { span => #[allow(deprecated)] });
+            }
+
+            let this = format_ident!("{}", unique_name("This", &used), span = span);
+            let (_, trait_ty_generics, _) = trait_generics.split_for_impl();
+            // the alias is generic over the trait's parameters too, right after the implementor
+            let mut ty_generics = generics.clone();
+            for (i, param) in trait_generics.params.iter().enumerate() {
+                ty_generics.params.insert(i, param.clone());
+            }
+            ty_generics.params.insert(0, parse_quote_spanned! { span => #this: #ty_sized #ty_lt #trait_ident #trait_ty_generics });
+            let (impl_ty_generics, _, _) = ty_generics.split_for_impl();
+
+            let mut self_generics = ty_generics.clone();
+            *self_generics.params.first_mut().unwrap() = GenericParam::Type(TypeParam {
+                attrs: Default::default(),
+                ident: format_ident!("Self", span = span),
+                colon_token: Default::default(),
+                bounds: Default::default(),
+                eq_token: Default::default(),
+                default: Default::default(),
+            });
+            let (_, ty_ty_generics, _) = self_generics.split_for_impl();
+
+            let self_replace = SelfReplace {
+                ident: this.clone(),
+                trait_ident,
+                trait_generics: trait_ty_generics.to_token_stream(),
+                assoc_types,
+            };
+
+            // the aliases are checked on their own, so they need the method's and the trait's bounds (i.e. `Self: Sync` for a `Send` future)
+            let mut alias_where = generics.where_clause.clone();
+            if let Some(trait_where) = &trait_generics.where_clause {
+                alias_where.get_or_insert_with(|| WhereClause { where_token: Default::default(), predicates: Punctuated::new() })
+                    .predicates.extend(trait_where.predicates.iter().cloned());
+            }
+            if let Some(where_clause) = &mut alias_where {
+                replace_self_predicates(where_clause.predicates.iter_mut(), &self_replace);
+            }
+
+            let mut output_types = Vec::new();
+            let mut defines = vec![ty_ident.clone()];
+            replace_bounds_impl_traits(future_bounds.iter_mut(), &mut |TypeImplTrait { mut bounds, .. }| {
+                let output_ident = format_ident!("{trait_ident}{}Default", output_ident(&future_name, output_types.len()), span = ident.span());
+                replace_self_bounds(bounds.iter_mut(), &self_replace);
+                output_types.push(quote_spanned! { span =>
+                    #(#alias_mirrored)*
+                    #[doc(hidden)]
+                    #vis type #output_ident #impl_ty_generics #alias_where = impl #bounds;
+                });
+                defines.push(output_ident.clone());
+                parse_quote_spanned! { span => #output_ident #ty_ty_generics }
+            });
+
+            let tokens = match asyncness {
+                Some(_) => {
+                    let body = future_body(span, &asyncness, &bindings, future_output(&future_bounds).filter(|_| output_types.is_empty()), &block);
+                    quote_spanned! { span =>{
+                        return #body
+                    }}
+                },
+                None => {
+                    let stmts = &block.stmts;
+                    quote_spanned! { span =>{
+                        #(#bindings)*
+                        #(#stmts)*
+                    }}
+                }
+            };
+
+            let mut opaque_bounds = future_bounds.clone();
+            replace_self_bounds(opaque_bounds.iter_mut(), &self_replace);
+
+            let opaque = quote_spanned! { span =>
+                #(#output_types)*
+
+                #(#alias_mirrored)*
+                #[doc(hidden)]
+                #vis type #ty_ident #impl_ty_generics #alias_where = impl #ty_lt #opaque_bounds;
+            };
+
+            (Some(tokens), quote_spanned! { span => #ty_ident #ty_ty_generics }, Some(opaque), defines)
+        },
+
+        None => {
+            (None, quote_spanned! { span => Self::#future_name #ty_generics }, None, Vec::new())
+        }
+    };
+
+    let associated_type = match &future_default {
+        Some(_) => None,
+        None => {
+            let mut output_types = Vec::new();
+            replace_bounds_impl_traits(future_bounds.iter_mut(), &mut |TypeImplTrait { bounds, .. }| {
+                let output_ident = output_ident(&future_name, output_types.len());
+                output_types.push(quote_spanned! { span =>
+                    #(#mirrored)*
+                    type #output_ident #impl_generics: #bounds #where_generics;
+                });
+                parse_quote_spanned! { span => Self::#output_ident #ty_generics }
+            });
+
+            Some(quote_spanned! { span =>
+                #(#output_types)*
+                #(#mirrored)*
+                type #future_name #impl_generics: #life #add_token #future_bounds #where_generics;
+            })
+        }
+    };
+
+    let method = quote_spanned! { span =>
+        #(#attrs)*
+        #constness #unsafety #abi #fn_token #ident #impl_generics (#inputs #variadic) -> #return_type #where_generics #future_default #semi_token
+    };
+
+    // newer nightlies require the functions defining a type alias' opaque type to say so
+    let method = match defines.is_empty() {
+        true => method,
+        false => quote_spanned! { span =>
+            ::fast_async_trait::__define_opaque! {
+                [#(#defines),*]
+                #method
+            }
+        }
+    };
+
+    let tokens = quote_spanned! { span =>
+        #associated_type
+        #method
+    };
+
+    return (tokens, extra)
+}
+
+/// Expands a single impl item. Async methods (and methods returning `impl Future`) become the definition of their associated future type
+/// and a method returning it (or just a method returning a boxed future, if annotated with `#[boxed]`), while any other item is returned untouched.
+#[inline]
+pub fn impl_fn (sig: ImplItem) -> TokenStream {
+    return match sig {
+        ImplItem::Method(method) if (method.sig.asyncness.is_some() || returns_future(&method.sig.output)) && is_boxed(&method.attrs) => impl_boxed_fn(method, None),
+        ImplItem::Method(method) if method.sig.asyncness.is_some() || returns_future(&method.sig.output) => impl_async_fn(method),
+        other => other.to_token_stream()
+    }
+}
+
+fn impl_async_fn (method: ImplItemMethod) -> TokenStream {
+    let span = method.sig.span();
+    let used = used_idents(method.to_token_stream());
+    let ImplItemMethod { mut attrs, vis, defaultness, sig: Signature { constness, asyncness, unsafety, abi, fn_token, ident, mut generics, mut inputs, variadic, output, .. }, block } = method;
+    let mirrored = mirrored_attrs(&attrs, false);
+    let detached = take_attr(&mut attrs, "detached");
+
+    let future_name = format_ident!("{}", to_pascal_case(&ident.to_string()), span = ident.span());
+    let mut future_bounds = future_bounds(&asyncness, output, span);
+
+    let (life, _, output_lt) = future_generics(&mut inputs, &mut generics, &used);
+    let bindings = normalize_inputs(inputs.iter_mut());
+    if let Err(e) = elide_output(future_bounds.iter_mut(), output_lt.as_ref()) {
+        return e.into_compile_error();
+    }
+    // repeats the bounds the trait adds to the method
+    if detached {
+        if let Err(e) = require_static(&ident, &mut generics) {
+            return e.into_compile_error();
+        }
+    }
+    let add_token = match life.is_empty() {
+        true => None,
+        false => Some(<Token![+]>::default())
+    };
+    let (impl_generics, ty_generics, where_generics) = generics.split_for_impl();
+
+    let mut output_types = Vec::new();
+    replace_bounds_impl_traits(future_bounds.iter_mut(), &mut |TypeImplTrait { bounds, .. }| {
+        let output_ident = output_ident(&future_name, output_types.len());
+        output_types.push(quote_spanned! { span =>
+            #(#mirrored)*
+            type #output_ident #impl_generics = impl #bounds #where_generics;
+        });
+        parse_quote_spanned! { span => Self::#output_ident #ty_generics }
+    });
+
+    let body = match asyncness {
+        Some(_) => {
+            let body = future_body(span, &asyncness, &bindings, future_output(&future_bounds).filter(|_| output_types.is_empty()), &block);
+            quote_spanned! { span =>
+                return #body
+            }
+        },
+        None => {
+            let stmts = &block.stmts;
+            quote_spanned! { span =>
+                #(#bindings)*
+                #(#stmts)*
+            }
+        }
+    };
+
+    quote_spanned! { span =>
+        #(#output_types)*
+        #(#mirrored)*
+        type #future_name #impl_generics = impl #life #add_token #future_bounds #where_generics;
+
+        #(#attrs)*
+        #vis #defaultness #constness #unsafety #abi #fn_token #ident #impl_generics (#inputs #variadic) -> Self::#future_name #ty_generics #where_generics {
+            #body
+        }
+    }
+}
+
+/// Checks whether the method is written as `fn foo (..) -> impl Future<Output = T>`
+pub fn returns_future (output: &ReturnType) -> bool {
+    if let ReturnType::Type(_, ty) = output {
+        if let Type::ImplTrait(TypeImplTrait { bounds, .. }) = &**ty {
+            return bounds.iter().any(|bound| matches!(
+                bound,
+                TypeParamBound::Trait(TraitBound { path, .. }) if path.segments.last().is_some_and(|x| x.ident == "Future")
+            ))
+        }
+    }
+
+    return false
+}
+
+/// Returns the bounds of the method's future, either built from the async method's output or taken verbatim from `-> impl Future<Output = T>`
+fn future_bounds (asyncness: &Option<Token![async]>, output: ReturnType, span: Span) -> Punctuated<TypeParamBound, Token![+]> {
+    return match (asyncness, output) {
+        (Some(_), ReturnType::Default) => parse_quote_spanned! { span => ::core::future::Future<Output = ()> },
+        (Some(_), ReturnType::Type(_, ty)) => parse_quote_spanned! { span => ::core::future::Future<Output = #ty> },
+        (None, ReturnType::Type(_, ty)) => match *ty {
+            Type::ImplTrait(TypeImplTrait { bounds, .. }) => bounds,
+            _ => unreachable!()
+        },
+        (None, ReturnType::Default) => unreachable!()
+    }
+}
+
+/// Returns the `Output` type of the future's bounds, if specified
+fn future_output (bounds: &Punctuated<TypeParamBound, Token![+]>) -> Option<&Type> {
+    return bounds.iter().find_map(|bound| match bound {
+        TypeParamBound::Trait(TraitBound { path, .. }) => match &path.segments.last()?.arguments {
+            PathArguments::AngleBracketed(AngleBracketedGenericArguments { args, .. }) => args.iter().find_map(|arg| match arg {
+                GenericArgument::Binding(Binding { ident, ty, .. }) if ident == "Output" => Some(ty),
+                _ => None
+            }),
+            _ => None
+        },
+        _ => None
+    })
+}
+
+/// Builds the future returned by an async method. When the output is nameable, it's used as a type hint, so that
+/// returned values are coerced just like they would be on a regular async function (i.e. `Box<dyn Trait>`).
+fn future_body (span: Span, asyncness: &Option<Token![async]>, bindings: &[TokenStream], output: Option<&Type>, block: &Block) -> TokenStream {
+    let ret = Ident::new("__ret", span.resolved_at(Span::mixed_site()));
+    return match output {
+        Some(output) => quote_spanned! { span =>
+            #asyncness move {
+                #(#bindings)*
+                if let ::core::option::Option::Some(#ret) = ::core::option::Option::None::<#output> {
+                    return #ret
+                }
+                #[allow(clippy::diverging_sub_expression)]
+                let #ret: #output = #block;
+                #[allow(unreachable_code)]
+                #ret
+            }
+        },
+        None => {
+            let stmts = &block.stmts;
+            quote_spanned! { span =>
+                #asyncness move {
+                    #(#bindings)*
+                    #(#stmts)*
+                }
+            }
+        }
+    }
+}
+
+/// Names the lifetimes the future of a method borrows from, adding them to `fn_generics`: the receiver's (along with a `Self: 'lt` bound)
+/// and, without a borrowed receiver, the one of the only borrowed argument. New lifetimes are named to avoid the `used` identifiers (see [`used_idents`]).
+///
+/// Returns the lifetime bounds of the future, the receiver's lifetime and the lifetime that elided lifetimes in the output resolve to.
+pub fn future_generics (inputs: &mut Punctuated<FnArg, Token![,]>, fn_generics: &mut Generics, used: &HashSet<String>) -> (Punctuated<TokenStream, Token![+]>, Option<Lifetime>, Option<Lifetime>) {
+    // Reciever generics
+    let mut result = None;
+    for input in inputs.iter_mut() {
+        if let FnArg::Receiver(Receiver { attrs, reference, .. }) = input {            
+            match reference {
+                Some((x, lt @ None)) => {
+                    let lifetime = Lifetime::new(&format!("'{}", unique_name("__self__", used)), x.span);
+                    *lt = Some(lifetime.clone());
+                    result = Some(lifetime.clone());
+
+                    fn_generics.params.insert(0, LifetimeDef {
+                        attrs: attrs.clone(),
+                        lifetime: lifetime.clone(),
+                        colon_token: Default::default(),
+                        bounds: Default::default(),
+                    }.into());
+
+                    fn_generics.make_where_clause().predicates.push(parse_quote_spanned! { x.span => Self: #lt });
+                },
+
+                Some((x, Some(lt))) => {
+                    result = Some(lt.clone());
+                    fn_generics.make_where_clause().predicates.push(parse_quote_spanned! { x.span => Self: #lt });
+                },
+
+                _ => {}
+            }
+            
+            break;
+        }
+    }
+
+    // Output lifetime, as per the lifetime elision rules
+    let output = match &result {
+        Some(lt) => Some(lt.clone()),
+        None => input_lifetime(inputs, fn_generics, used)
+    };
+
+    let tokens = fn_generics.lifetimes().map(|LifetimeDef { attrs, lifetime, .. }| 
+        quote! { #(#attrs)* #lifetime }
+    ).collect();
+
+    return (tokens, result, output);
+}
+
+/// Returns the lifetime of the only borrowed argument, naming it if it was elided
+fn input_lifetime (inputs: &mut Punctuated<FnArg, Token![,]>, fn_generics: &mut Generics, used: &HashSet<String>) -> Option<Lifetime> {
+    let mut count = 0usize;
+    let mut found = None;
+
+    for input in inputs.iter_mut() {
+        if let FnArg::Typed(PatType { ty, .. }) = input {
+            visit_lifetimes(ty, &mut |lt, span| {
+                count += 1;
+                found = Some((lt.clone(), span));
+            });
+        }
+    }
+
+    match (count, found) {
+        (1, Some((Some(lt), _))) => Some(lt),
+        (1, Some((None, span))) => {
+            let lifetime = Lifetime::new(&format!("'{}", unique_name("__arg__", used)), span);
+            for input in inputs.iter_mut() {
+                if let FnArg::Typed(PatType { ty, .. }) = input {
+                    visit_lifetimes(ty, &mut |lt, _| *lt = Some(lifetime.clone()));
+                }
+            }
+
+            fn_generics.params.insert(0, LifetimeDef::new(lifetime.clone()).into());
+            Some(lifetime)
+        },
+        _ => None
+    }
+}
+
+/// Binds every elided lifetime of the future's bounds to `lifetime`
+fn elide_output<'a> (bounds: impl IntoIterator<Item = &'a mut TypeParamBound>, lifetime: Option<&Lifetime>) -> Result<()> {
+    let mut result = Ok(());
+    visit_bounds_lifetimes(bounds, &mut |lt, span| {
+        if lt.is_some() { return }
+        match lifetime {
+            Some(lifetime) => *lt = Some(Lifetime::new(&lifetime.to_string(), span)),
+            None => {
+                let e = Error::new(span, "missing lifetime specifier: the output lifetime cannot be elided without a borrowed receiver or a single borrowed argument");
+                match &mut result {
+                    Ok(_) => result = Err(e),
+                    Err(prev) => prev.combine(e)
+                }
+            }
+        }
+    });
+    return result
+}
+
+/// Visits every lifetime position of `ty` outside of higher-ranked contexts (`fn` pointers and `Fn` sugar), with `None` representing an elided lifetime
+fn visit_lifetimes<F: FnMut(&mut Option<Lifetime>, Span)> (ty: &mut Type, f: &mut F) {
+    match ty {
+        Type::Array(TypeArray { elem, .. }) | 
+        Type::Paren(TypeParen { elem, .. }) |
+        Type::Group(TypeGroup { elem, .. }) |
+        Type::Ptr(TypePtr { elem, .. })     |
+        Type::Slice(TypeSlice { elem, .. }) => visit_lifetimes(elem, f),
+
+        Type::Reference(TypeReference { and_token, lifetime, elem, .. }) => {
+            let span = match lifetime {
+                Some(lt) => lt.span(),
+                None => and_token.span
+            };
+            visit_lifetime_slot(lifetime, span, f);
+            visit_lifetimes(elem, f)
+        },
+
+        Type::ImplTrait(TypeImplTrait { bounds, .. }) |
+        Type::TraitObject(TypeTraitObject { bounds, .. }) => visit_bounds_lifetimes(bounds.iter_mut(), f),
+        Type::Tuple(TypeTuple { elems, .. }) => elems.iter_mut().for_each(|x| visit_lifetimes(x, f)),
+        Type::Path(TypePath { qself, path }) => {
+            if let Some(QSelf { ty, .. }) = qself { visit_lifetimes(ty, f) }
+            visit_path_lifetimes(path, f)
+        },
+        _ => {}
+    }
+}
+
+#[inline]
+fn visit_bounds_lifetimes<'a, F: FnMut(&mut Option<Lifetime>, Span)> (bounds: impl IntoIterator<Item = &'a mut TypeParamBound>, f: &mut F) {
+    for bound in bounds {
+        match bound {
+            TypeParamBound::Trait(TraitBound { path, .. }) => visit_path_lifetimes(path, f),
+            TypeParamBound::Lifetime(lt) => visit_lifetime(lt, f)
+        }
+    }
+}
+
+fn visit_path_lifetimes<F: FnMut(&mut Option<Lifetime>, Span)> (Path { segments, .. }: &mut Path, f: &mut F) {
+    for PathSegment { arguments, .. } in segments.iter_mut() {
+        if let PathArguments::AngleBracketed(AngleBracketedGenericArguments { args, .. }) = arguments {
+            for arg in args {
+                match arg {
+                    GenericArgument::Lifetime(lt) => visit_lifetime(lt, f),
+                    GenericArgument::Binding(Binding { ty, .. }) | GenericArgument::Type(ty) => visit_lifetimes(ty, f),
+                    GenericArgument::Constraint(Constraint { bounds, .. }) => visit_bounds_lifetimes(bounds.iter_mut(), f),
+                    GenericArgument::Const(_) => {},
+                }
+            }
+        }
+    }
+}
+
+#[inline]
+fn visit_lifetime<F: FnMut(&mut Option<Lifetime>, Span)> (lt: &mut Lifetime, f: &mut F) {
+    let mut slot = match lt.ident == "_" {
+        true => None,
+        false => Some(lt.clone())
+    };
+
+    f(&mut slot, lt.span());
+    if let Some(slot) = slot {
+        *lt = slot;
+    }
+}
+
+#[inline]
+fn visit_lifetime_slot<F: FnMut(&mut Option<Lifetime>, Span)> (lt: &mut Option<Lifetime>, span: Span, f: &mut F) {
+    if matches!(lt, Some(x) if x.ident == "_") {
+        *lt = None;
+    }
+    f(lt, span)
+}
+
+/// Replaces pattern arguments with plain bindings, returning the `let` statements that destructure them inside the future.
+/// The bindings' names don't collide with the other arguments even without hygiene (i.e. once expanded to text).
+fn normalize_inputs<'a> (inputs: impl IntoIterator<Item = &'a mut FnArg>) -> Vec<TokenStream> {
+    let inputs = inputs.into_iter().collect::<Vec<_>>();
+    let used = used_idents(quote! { #(#inputs)* });
+    let mut result = Vec::new();
+
+    for (i, input) in inputs.into_iter().enumerate() {
+        let pat = match input {
+            FnArg::Typed(PatType { pat, .. }) => pat,
+            FnArg::Receiver(_) => continue
+        };
+
+        match &mut **pat {
+            Pat::Ident(PatIdent { by_ref: None, mutability: None, subpat: None, .. }) | Pat::Wild(_) => {},
+
+            Pat::Ident(PatIdent { by_ref: None, mutability: mutability @ Some(_), subpat: None, ident, .. }) => {
+                result.push(quote! { let #mutability #ident = #ident; });
+                *mutability = None;
+            },
+
+            other => {
+                let ident = Ident::new(&unique_name(&format!("__arg{i}"), &used), other.span().resolved_at(Span::mixed_site()));
+                result.push(quote! { let #other = #ident; });
+                *other = Pat::Ident(PatIdent {
+                    attrs: Default::default(),
+                    by_ref: None,
+                    mutability: None,
+                    ident,
+                    subpat: None,
+                });
+            }
+        }
+    }
+
+    return result
+}
+
+/// Names every argument, returning the names in order (see [`normalize_inputs`])
+fn normalize_args<'a> (inputs: impl IntoIterator<Item = &'a mut FnArg>) -> Vec<Ident> {
+    let inputs = inputs.into_iter().collect::<Vec<_>>();
+    let used = used_idents(quote! { #(#inputs)* });
+    let mut result = Vec::new();
+
+    for (i, input) in inputs.into_iter().enumerate() {
+        let pat = match input {
+            FnArg::Typed(PatType { pat, .. }) if !matches!(&**pat, Pat::Ident(PatIdent { ident, .. }) if ident == "self") => pat,
+            _ => continue
+        };
+
+        let ident = match &**pat {
+            Pat::Ident(PatIdent { by_ref: None, subpat: None, ident, .. }) => ident.clone(),
+            other => Ident::new(&unique_name(&format!("__arg{i}"), &used), other.span().resolved_at(Span::mixed_site()))
+        };
+
+        **pat = Pat::Ident(PatIdent {
+            attrs: Default::default(),
+            by_ref: None,
+            mutability: None,
+            ident: ident.clone(),
+            subpat: None,
+        });
+        result.push(ident);
+    }
+
+    return result
+}
+
+/// Explicit type and const arguments of a call to a method with the given generics, if any
+fn turbofish (generics: &Generics) -> Option<TokenStream> {
+    let params = generics.params.iter()
+        .filter_map(|x| match x {
+            GenericParam::Type(TypeParam { ident, .. }) | GenericParam::Const(ConstParam { ident, .. }) => Some(ident),
+            GenericParam::Lifetime(_) => None
+        })
+        .collect::<Vec<_>>();
+
+    return match params.is_empty() {
+        true => None,
+        false => Some(quote! { ::<#(#params),*> })
+    }
+}
+
+/// Qualifies the associated types of the trait inside a signature (see [`SelfReplace`])
+fn qualify_signature (inputs: &Punctuated<FnArg, Token![,]>, output: &ReturnType, generics: &Generics, replace: &SelfReplace) -> (Punctuated<FnArg, Token![,]>, ReturnType, Generics) {
+    let mut inputs = inputs.clone();
+    for input in inputs.iter_mut() {
+        if let FnArg::Typed(PatType { ty, .. }) = input {
+            replace_self_ty(ty, replace);
+        }
+    }
+
+    let mut output = output.clone();
+    if let ReturnType::Type(_, ty) = &mut output {
+        replace_self_ty(ty, replace);
+    }
+
+    let mut generics = generics.clone();
+    if let Some(where_clause) = &mut generics.where_clause {
+        replace_self_predicates(where_clause.predicates.iter_mut(), replace);
+    }
+
+    return (inputs, output, generics)
+}
+
+/// Returns every identifier (and lifetime name) inside `tokens`
+pub fn used_idents (tokens: TokenStream) -> HashSet<String> {
+    fn collect (tokens: TokenStream, result: &mut HashSet<String>) {
+        for token in tokens {
+            match token {
+                TokenTree::Ident(ident) => { result.insert(ident.to_string()); },
+                TokenTree::Group(group) => collect(group.stream(), result),
+                TokenTree::Punct(_) | TokenTree::Literal(_) => {}
+            }
+        }
+    }
+
+    let mut result = HashSet::new();
+    collect(tokens, &mut result);
+    return result
+}
+
+/// Returns `base`, or a numbered variation of it, so that it doesn't collide with any of the `used` identifiers.
+/// The number goes before the trailing underscores, so that names like `__self__` stay snake case (`__self1__`).
+fn unique_name (base: &str, used: &HashSet<String>) -> String {
+    if !used.contains(base) {
+        return base.to_string()
+    }
+
+    let name = base.trim_end_matches('_');
+    let suffix = &base[name.len()..];
+    return (1..).map(|i| format!("{name}{i}{suffix}"))
+        .find(|x| !used.contains(x))
+        .unwrap()
+}
+
+#[inline]
+fn output_ident (future_name: &Ident, idx: usize) -> Ident {
+    return match idx {
+        0 => format_ident!("{future_name}Output", span = future_name.span()),
+        idx => format_ident!("{future_name}Output{idx}", span = future_name.span())
+    }
+}
+
+/// Replaces every `impl Trait` inside `ty` (innermost first) with the type returned by `f`
+fn replace_impl_traits<F: FnMut(TypeImplTrait) -> Type> (ty: &mut Type, f: &mut F) {
+    match ty {
+        Type::Array(TypeArray { elem, .. }) | 
+        Type::Paren(TypeParen { elem, .. }) |
+        Type::Group(TypeGroup { elem, .. }) |
+        Type::Ptr(TypePtr { elem, .. })     |
+        Type::Slice(TypeSlice { elem, .. }) |
+        Type::Reference(TypeReference { elem, .. }) => replace_impl_traits(elem, f),
+        Type::Tuple(TypeTuple { elems, .. }) => elems.iter_mut().for_each(|x| replace_impl_traits(x, f)),
+        Type::Path(TypePath { path, .. }) => replace_path_impl_traits(path, f),
+        Type::TraitObject(TypeTraitObject { bounds, .. }) => replace_bounds_impl_traits(bounds.iter_mut(), f),
+
+        Type::ImplTrait(TypeImplTrait { bounds, .. }) => {
+            replace_bounds_impl_traits(bounds.iter_mut(), f);
+            if let Type::ImplTrait(impl_trait) = core::mem::replace(ty, Type::Verbatim(TokenStream::new())) {
+                *ty = f(impl_trait);
+            }
+        },
+
+        _ => {}
+    }
+}
+
+#[inline]
+fn replace_bounds_impl_traits<'a, F: FnMut(TypeImplTrait) -> Type> (bounds: impl IntoIterator<Item = &'a mut TypeParamBound>, f: &mut F) {
+    for bound in bounds {
+        if let TypeParamBound::Trait(TraitBound { path, .. }) = bound {
+            replace_path_impl_traits(path, f);
+        }
+    }
+}
+
+fn replace_path_impl_traits<F: FnMut(TypeImplTrait) -> Type> (Path { segments, .. }: &mut Path, f: &mut F) {
+    for PathSegment { arguments, .. } in segments.iter_mut() {
+        if let PathArguments::AngleBracketed(AngleBracketedGenericArguments { args, .. }) = arguments {
+            for arg in args {
+                if let GenericArgument::Binding(Binding { ty, .. }) | GenericArgument::Type(ty) = arg {
+                    replace_impl_traits(ty, f);
+                }
+            }
+        }
+    }
+}
+
+/// Removes the attributes of a method of a native implementation that only apply to associated futures, reporting them
+fn take_native_attrs (item: &mut ImplItem) -> TokenStream {
+    let mut result = TokenStream::new();
+    if let ImplItem::Method(method) = item {
+        method.attrs.retain(|attr| match ["boxed", "max_size", "detached"].into_iter().find(|x| attr.path.is_ident(x)) {
+            Some(name) => {
+                result.extend(syn::Error::new_spanned(attr, format!(
+                    "`#[{name}]` can't be used in native implementations, since their async methods don't have associated futures"
+                )).into_compile_error());
+                false
+            },
+            None => true
+        });
+    }
+    return result
+}
+
+/// Removes the `#[name]` attributes of a method (i.e. `#[detached]`), returning whether it had any
+fn take_attr (attrs: &mut Vec<Attribute>, name: &str) -> bool {
+    let len = attrs.len();
+    attrs.retain(|x| !x.path.is_ident(name));
+    return attrs.len() != len
+}
+
+/// Requires `Self` and the type parameters of a method to be `'static`, since its `'static` future captures them.
+/// Fails if the future borrows anything (i.e. the method has a lifetime).
+fn require_static (ident: &Ident, generics: &mut Generics) -> Result<()> {
+    if let Some(LifetimeDef { lifetime, .. }) = generics.lifetimes().next() {
+        return Err(syn::Error::new(lifetime.span(), format!(
+            "the future of method `{ident}` must be `'static`, so it can't borrow `self` or its arguments (i.e. take `self: Arc<Self>` instead of `&self`)"
+        )))
+    }
+
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote! { 'static });
+    }
+    generics.make_where_clause().predicates.push(parse_quote! { Self: 'static });
+    return Ok(())
+}
+
+/// Attributes of a method that also apply to the items generated for it: conditional compilation, lints, docs and, if `deprecated` is set, deprecation.
+/// Lint expectations stay on the method, since the generated items can't fulfill them.
+fn mirrored_attrs (attrs: &[Attribute], deprecated: bool) -> Vec<Attribute> {
+    return attrs.iter()
+        .filter_map(|attr| match attr.path.get_ident() {
+            Some(ident) if ident == "cfg_attr" => mirrored_cfg_attr(attr, deprecated),
+            Some(ident) if is_mirrored(ident, deprecated) => Some(attr.clone()),
+            _ => None
+        })
+        .collect()
+}
+
+#[inline]
+fn is_mirrored (ident: &Ident, deprecated: bool) -> bool {
+    return MIRRORED_ATTRS.iter().any(|x| ident == x) || (deprecated && ident == "deprecated")
+}
+
+/// Keeps the mirrored attributes of a `#[cfg_attr(predicate, attrs..)]`, if any
+fn mirrored_cfg_attr (attr: &Attribute, deprecated: bool) -> Option<Attribute> {
+    let args = match attr.tokens.clone().into_iter().next() {
+        Some(TokenTree::Group(group)) if group.delimiter() == proc_macro2::Delimiter::Parenthesis => group.stream(),
+        _ => return None
+    };
+
+    let mut parts = vec![TokenStream::new()];
+    for tt in args {
+        match tt {
+            TokenTree::Punct(punct) if punct.as_char() == ',' => parts.push(TokenStream::new()),
+            other => parts.last_mut().unwrap().extend(core::iter::once(other))
+        }
+    }
+
+    let mut parts = parts.into_iter().filter(|x| !x.is_empty());
+    let predicate = parts.next()?;
+    let attrs = parts
+        .filter(|x| matches!(x.clone().into_iter().next(), Some(TokenTree::Ident(ident)) if is_mirrored(&ident, deprecated)))
+        .collect::<Vec<_>>();
+
+    if attrs.is_empty() {
+        return None
+    }
+
+    let pound_token = attr.pound_token;
+    let path = &attr.path;
+    return Some(parse_quote! { #pound_token[#path(#predicate, #(#attrs),*)] })
+}
+
+const MIRRORED_ATTRS: &[&str] = &["cfg", "allow", "warn", "deny", "forbid", "doc"];
+
+fn to_pascal_case (s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut upper = true;
+
+    for c in s.chars() {
+        match c {
+            '_' => upper = true,
+            c if upper => {
+                result.extend(c.to_uppercase());
+                upper = false
+            },
+            c => result.push(c)
+        }
+    }
+
+    return result
+}
+
+/// Replacement of `Self` inside the free type aliases generated for default methods
+struct SelfReplace<'a> {
+    ident: Ident,
+    trait_ident: &'a Ident,
+    /// Generic arguments of the trait, i.e. `<T>`
+    trait_generics: TokenStream,
+    assoc_types: &'a [Ident],
+}
+
+fn replace_self_ty (ty: &mut Type, replace: &SelfReplace) {
+    if let Some(qualified) = qualify_assoc_type(ty, replace) {
+        *ty = qualified;
+    }
+
+    match ty {
+        Type::Array(TypeArray { elem, .. }) | 
+        Type::Paren(TypeParen { elem, .. }) |
+        Type::Group(TypeGroup { elem, .. }) |
+        Type::Ptr(TypePtr { elem, .. })     |
+        Type::Slice(TypeSlice { elem, .. }) |
+        Type::Reference(TypeReference { elem, .. }) => replace_self_ty(elem, replace),
+        
+        Type::BareFn(TypeBareFn { inputs, output, .. }) => {
+            inputs.iter_mut().for_each(|x| replace_self_ty(&mut x.ty, replace));
+            if let ReturnType::Type(_, ty) = output { replace_self_ty(ty, replace) };
+        },
+        Type::ImplTrait(TypeImplTrait { bounds, .. }) |
+        Type::TraitObject(TypeTraitObject { bounds, .. }) => replace_self_bounds(bounds.iter_mut(), replace),
+        Type::Tuple(TypeTuple { elems, .. }) => elems.iter_mut()
+            .for_each(|x| replace_self_ty(x, replace)),
+        Type::Path(TypePath { qself, path }) => {
+            if let Some(QSelf { ty, .. }) = qself { replace_self_ty(ty, replace) }
+            replace_self(path, replace)
+        },
+        // macros and verbatim types can't be looked into, and are left as they are
+        _ => {}
+    }
+}
+
+#[inline]
+fn replace_self_bounds<'a> (bounds: impl IntoIterator<Item = &'a mut TypeParamBound>, replace: &SelfReplace) {
+    for bound in bounds {
+        if let TypeParamBound::Trait(bound) = bound {
+            replace_self(&mut bound.path, replace);
+        }
+    }
+}
+
+#[inline]
+fn replace_self_predicates<'a> (predicates: impl IntoIterator<Item = &'a mut WherePredicate>, replace: &SelfReplace) {
+    for predicate in predicates {
+        if let WherePredicate::Type(PredicateType { bounded_ty, bounds, .. }) = predicate {
+            replace_self_ty(bounded_ty, replace);
+            replace_self_bounds(bounds.iter_mut(), replace);
+        }
+    }
+}
+
+fn replace_self (Path { segments, .. }: &mut Path, replace: &SelfReplace) {
+    for PathSegment { ident, arguments } in segments.iter_mut() {
+        if ident == "Self" {
+            *ident = replace.ident.clone();
+        }
+
+        match arguments {
+            PathArguments::AngleBracketed(AngleBracketedGenericArguments { args, .. }) => {
+                for arg in args {
+                    match arg {
+                        GenericArgument::Binding(Binding { ty, .. }) | GenericArgument::Type(ty) => replace_self_ty(ty, replace),
+                        GenericArgument::Constraint(Constraint { bounds, .. }) => replace_self_bounds(bounds.iter_mut(), replace),
+                        GenericArgument::Const(_) | GenericArgument::Lifetime(_) => {},
+                    }
+                }
+            },
+
+            PathArguments::Parenthesized(ParenthesizedGenericArguments { inputs, output, .. }) => {
+                inputs.iter_mut().for_each(|x| replace_self_ty(x, replace));
+                if let ReturnType::Type(_, ty) = output { replace_self_ty(ty, replace) }
+            },
+            
+            PathArguments::None => {}
+        }
+    }
+}
+
+/// Qualifies `Self::Item` as `<Self as Trait>::Item` when `Item` is declared by the trait itself, so that
+/// the generated alias doesn't rely on `Self::Item` being unambiguous (i.e. when a supertrait declares the same name).
+fn qualify_assoc_type (ty: &Type, replace: &SelfReplace) -> Option<Type> {
+    let path = match ty {
+        Type::Path(TypePath { qself: None, path }) if path.leading_colon.is_none() => path,
+        _ => return None
+    };
+
+    let mut segments = path.segments.iter();
+    match (segments.next(), segments.next()) {
+        (Some(PathSegment { ident, arguments: PathArguments::None }), Some(PathSegment { ident: assoc, .. }))
+            if ident == "Self" && replace.assoc_types.contains(assoc) => {},
+        _ => return None
+    }
+
+    let SelfReplace { trait_ident, trait_generics, .. } = replace;
+    let rest = path.segments.iter().skip(1);
+    return Some(parse_quote! { <Self as #trait_ident #trait_generics>::#(#rest)::* })
+}
\ No newline at end of file
@@ -0,0 +1,176 @@
+use std::hash::{Hash, Hasher};
+use proc_macro2::{TokenStream, Literal};
+use quote::{quote, quote_spanned, format_ident, ToTokens};
+use syn::{*, punctuated::Punctuated, spanned::Spanned};
+use crate::{def::*, returns_future, future_generics, used_idents, boxed::{is_boxed, boxed_marker_arm}};
+
+/// Name of the method that `#[async_trait_impl]` adds to every implementation, so that forgetting it results in a missing item error
+const IMPL_REQUIRED: &str = "__async_trait_impl";
+
+/// Emits the hidden marker of a trait: a macro with the same name as the trait (so it's imported alongside it),
+/// which `#[async_trait_impl]` invokes to check every implemented method against the trait's definition, and to name the futures of `#[boxed]` methods.
+/// The markers of public traits are exported from the crate's root, so they're named after a hash of the trait's `source` (and of nothing else, so that
+/// every expansion of the same trait, be it by the macros, the build script API or the command-line tool, names its marker the same).
+pub fn define_marker (vis: &Visibility, trait_ident: &Ident, extra_bounds: &[TypeParamBound], items: &[AsyncTraitItem], source: &str) -> TokenStream {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    source.hash(&mut hasher);
+    quote!(#(#extra_bounds)*).to_string().hash(&mut hasher);
+    let marker_ident = format_ident!("__fast_async_trait_{}_{:016x}", trait_ident, hasher.finish());
+
+    let arms = items.iter().filter_map(|item| match item {
+        AsyncTraitItem::Method(AsyncTraitItemMethod { attrs, sig, .. }) => Some((attrs, sig)),
+        _ => None
+    }).map(|(attrs, sig)| {
+        let ident = &sig.ident;
+        let receiver = receiver_kind(&sig.inputs);
+        let receiver_ident = format_ident!("{receiver}");
+
+        let lifetimes = future_lifetimes(sig);
+        let lifetimes_msg = format!("the future of method `{ident}` borrows from {lifetimes} lifetime(s) in trait `{trait_ident}`, but from ");
+        let lifetimes_arm = quote_spanned! { sig.span() =>
+            ::core::compile_error! { ::core::concat!(#lifetimes_msg, $lifetimes, " in its implementation") }
+        };
+        let lifetimes = Literal::usize_unsuffixed(lifetimes);
+
+        let arms = match (sig.asyncness.is_some() || returns_future(&sig.output), is_boxed(attrs)) {
+            (true, true) => {
+                let sync_msg = format!("method `{ident}` is async in trait `{trait_ident}`, so its implementation must be async too");
+                let boxed_msg = format!("method `{ident}` is boxed in trait `{trait_ident}`, so its implementation must be `#[boxed]` too");
+                let receiver_msg = format!("method `{ident}` takes {} in trait `{trait_ident}`, but its implementation doesn't", describe_receiver(receiver));
+                let boxed_arm = boxed_marker_arm(extra_bounds, sig);
+                quote_spanned! { sig.span() =>
+                    (#ident boxed #receiver_ident #lifetimes) => {};
+                    (#ident boxed #receiver_ident $lifetimes:literal) => { #lifetimes_arm };
+                    (#ident boxed $receiver:tt $lifetimes:tt) => { ::core::compile_error! { #receiver_msg } };
+                    (#ident async $($tt:tt)*) => { ::core::compile_error! { #boxed_msg } };
+                    (#ident sync $($tt:tt)*) => { ::core::compile_error! { #sync_msg } };
+                    #boxed_arm
+                }
+            },
+
+            (true, false) => {
+                let sync_msg = format!("method `{ident}` is async in trait `{trait_ident}`, so its implementation must be async too");
+                let boxed_msg = format!("method `{ident}` isn't boxed in trait `{trait_ident}`, so its implementation can't be `#[boxed]`");
+                let receiver_msg = format!("method `{ident}` takes {} in trait `{trait_ident}`, but its implementation doesn't", describe_receiver(receiver));
+                quote_spanned! { sig.span() =>
+                    (#ident async #receiver_ident #lifetimes) => {};
+                    (#ident async #receiver_ident $lifetimes:literal) => { #lifetimes_arm };
+                    (#ident async $receiver:tt $lifetimes:tt) => { ::core::compile_error! { #receiver_msg } };
+                    (#ident sync $($tt:tt)*) => { ::core::compile_error! { #sync_msg } };
+                    (#ident boxed $($tt:tt)*) => { ::core::compile_error! { #boxed_msg } };
+                    (@boxed #ident $($tt:tt)*) => { ::core::compile_error! { #boxed_msg } };
+                }
+            },
+
+            (false, _) => {
+                let async_msg = format!("method `{ident}` isn't async in trait `{trait_ident}`, so its implementation can't be async");
+                quote_spanned! { sig.span() =>
+                    (#ident sync $($tt:tt)*) => {};
+                    (#ident $kind:ident $($tt:tt)*) => { ::core::compile_error! { #async_msg } };
+                }
+            }
+        };
+
+        return arms
+    });
+
+    // markers can only be exported from the crate's root, which would make those of private traits public
+    let export = match vis {
+        Visibility::Public(_) => Some(quote! { #[macro_export] }),
+        _ => None
+    };
+
+    quote! {
+        #[doc(hidden)]
+        #export
+        macro_rules! #marker_ident {
+            #(#arms)*
+            ($($tt:tt)*) => {};
+        }
+
+        #[doc(hidden)]
+        #[allow(unused_imports)]
+        #vis use #marker_ident as #trait_ident;
+    }
+}
+
+/// Hidden item added to the trait, implemented by `#[async_trait_impl]`
+pub fn define_impl_required () -> TokenStream {
+    let ident = format_ident!("{IMPL_REQUIRED}");
+    quote! {
+        #[doc(hidden)]
+        fn #ident () where Self: ::core::marker::Sized;
+    }
+}
+
+/// Implementation of the hidden item added by `#[async_trait_def]`
+pub fn impl_required () -> TokenStream {
+    let ident = format_ident!("{IMPL_REQUIRED}");
+    quote! {
+        #[doc(hidden)]
+        #[inline]
+        fn #ident () {}
+    }
+}
+
+/// Invokes the trait's marker for every implemented method
+pub fn check_marker (trait_path: &Path, items: &[ImplItem]) -> TokenStream {
+    let path = marker_path(trait_path);
+
+    let checks = items.iter().filter_map(|item| match item {
+        ImplItem::Method(ImplItemMethod { attrs, sig, .. }) => Some((attrs, sig)),
+        _ => None
+    }).map(|(attrs, sig)| {
+        let ident = &sig.ident;
+        let receiver = format_ident!("{}", receiver_kind(&sig.inputs));
+        let lifetimes = Literal::usize_unsuffixed(future_lifetimes(sig));
+        let kind = match (sig.asyncness.is_some() || returns_future(&sig.output), is_boxed(attrs)) {
+            (true, true) => format_ident!("boxed"),
+            (true, false) => format_ident!("async"),
+            (false, _) => format_ident!("sync")
+        };
+
+        quote_spanned! { sig.ident.span() =>
+            #path! { #ident #kind #receiver #lifetimes }
+        }
+    });
+
+    quote! {
+        #(#checks)*
+    }
+}
+
+/// Path of the marker of a trait, i.e. the trait's path without generic arguments
+pub fn marker_path (trait_path: &Path) -> Path {
+    let mut path = trait_path.clone();
+    path.segments.iter_mut().for_each(|x| x.arguments = PathArguments::None);
+    return path
+}
+
+/// Number of lifetimes the future of a method borrows from (see [`future_generics`])
+fn future_lifetimes (sig: &Signature) -> usize {
+    let used = used_idents(sig.to_token_stream());
+    let Signature { mut inputs, mut generics, .. } = sig.clone();
+    future_generics(&mut inputs, &mut generics, &used);
+    return generics.lifetimes().count()
+}
+
+pub fn receiver_kind (inputs: &Punctuated<FnArg, Token![,]>) -> &'static str {
+    return match inputs.first() {
+        Some(FnArg::Receiver(Receiver { reference: Some(_), mutability: Some(_), .. })) => "by_mut",
+        Some(FnArg::Receiver(Receiver { reference: Some(_), .. })) => "by_ref",
+        Some(FnArg::Receiver(Receiver { reference: None, .. })) => "by_value",
+        Some(FnArg::Typed(PatType { pat, .. })) if matches!(&**pat, Pat::Ident(PatIdent { ident, .. }) if ident == "self") => "custom",
+        _ => "none"
+    }
+}
+
+fn describe_receiver (kind: &str) -> &'static str {
+    return match kind {
+        "by_mut" => "`&mut self`",
+        "by_ref" => "`&self`",
+        "by_value" => "`self`",
+        "custom" => "a custom receiver",
+        _ => "no receiver"
+    }
+}
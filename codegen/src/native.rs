@@ -0,0 +1,131 @@
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned, format_ident};
+use syn::{*, punctuated::Punctuated, spanned::Spanned};
+use crate::*;
+
+/// Trait being implemented by [`define_native_blanket`]
+pub struct NativeTrait<'a> {
+    pub unsafety: Option<Token![unsafe]>,
+    pub ident: &'a Ident,
+    pub generics: &'a Generics,
+    pub supertraits: &'a Punctuated<TypeParamBound, Token![+]>,
+    pub items: &'a [AsyncTraitItem],
+    /// Whether the trait has the hidden item added by `#[async_trait_def]` (i.e. it isn't an auto trait)
+    pub impl_required: bool,
+}
+
+/// Emits the implementation of the trait for every implementor of `native`, an equivalent trait written with native `async fn`s.
+/// Every item is forwarded to the native trait, except for async methods with a default implementation.
+pub fn define_native_blanket (native: &Path, def: NativeTrait) -> TokenStream {
+    let NativeTrait { unsafety, ident: trait_ident, generics, supertraits, items, impl_required } = def;
+    let span = native.span();
+
+    let mut used = used_idents(quote! { #generics #supertraits #(#items)* });
+    used.insert(trait_ident.to_string());
+    let this = format_ident!("{}", unique_name("This", &used), span = span);
+
+    let assoc_types = items.iter()
+        .filter_map(|x| match x {
+            AsyncTraitItem::Type(TraitItemType { ident, .. }) => Some(ident.clone()),
+            _ => None
+        })
+        .collect::<Vec<_>>();
+    // the implementors see the associated types of both traits
+    let replace = SelfReplace {
+        ident: format_ident!("Self"),
+        trait_ident,
+        trait_generics: generics.split_for_impl().1.to_token_stream(),
+        assoc_types: &assoc_types,
+    };
+
+    let mut errors = Vec::new();
+    let items = items.iter()
+        .map(|item| match item {
+            AsyncTraitItem::Type(TraitItemType { attrs, type_token, ident, generics, semi_token, .. }) => {
+                let attrs = mirrored_attrs(attrs, false);
+                let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+                quote! { #(#attrs)* #type_token #ident #impl_generics = <#this as #native>::#ident #ty_generics #where_clause #semi_token }
+            },
+
+            AsyncTraitItem::Const(TraitItemConst { attrs, const_token, ident, colon_token, ty, semi_token, .. }) => {
+                let attrs = mirrored_attrs(attrs, false);
+                let mut ty = ty.clone();
+                replace_self_ty(&mut ty, &replace);
+                quote! { #(#attrs)* #const_token #ident #colon_token #ty = <#this as #native>::#ident #semi_token }
+            },
+
+            // default async methods return their own opaque type, which can't be replaced
+            AsyncTraitItem::Method(AsyncTraitItemMethod { sig, default: Some(_), .. }) if sig.asyncness.is_some() || returns_future(&sig.output) => TokenStream::new(),
+
+            AsyncTraitItem::Method(AsyncTraitItemMethod { attrs, sig, .. }) => {
+                let span = sig.span();
+                let mut sig = sig.clone();
+                let args = normalize_args(sig.inputs.iter_mut());
+                let (inputs, output, generics) = qualify_signature(&sig.inputs, &sig.output, &sig.generics, &replace);
+                sig.inputs = inputs;
+                sig.output = output;
+                sig.generics = generics;
+
+                let self_arg = match receiver_kind(&sig.inputs) {
+                    "none" => None,
+                    _ => Some(quote_spanned! { span => self, })
+                };
+
+                let ident = &sig.ident;
+                let turbofish = turbofish(&sig.generics);
+                let call = quote_spanned! { span => <Self as #native>::#ident #turbofish (#self_arg #(#args),*) };
+                let call = match sig.asyncness {
+                    Some(_) => quote_spanned! { span => #call.await },
+                    None => call
+                };
+                let call = match sig.unsafety {
+                    Some(_) => quote_spanned! { span => unsafe { #call } },
+                    None => call
+                };
+
+                let boxed = is_boxed(attrs);
+                let attrs = mirrored_attrs(attrs, false);
+                let method = parse_quote_spanned! { span =>
+                    #(#attrs)*
+                    #[inline]
+                    #sig {
+                        return #call
+                    }
+                };
+
+                match boxed {
+                    true => impl_boxed_fn(method, Some(&trait_ident.clone().into())),
+                    false => impl_fn(ImplItem::Method(method))
+                }
+            },
+
+            AsyncTraitItem::Macro(mac) => {
+                errors.push(Error::new_spanned(mac, "macro invocations can't be forwarded to the native trait").into_compile_error());
+                TokenStream::new()
+            },
+
+            // already reported by the trait itself
+            AsyncTraitItem::Verbatim(_) => TokenStream::new()
+        })
+        .collect::<Vec<_>>();
+
+    let (_, ty_generics, _) = generics.split_for_impl();
+    let mut impl_generics = generics.clone();
+    impl_generics.params.push(parse_quote_spanned! { span => #this: #native });
+    let (impl_generics, _, where_clause) = impl_generics.split_for_impl();
+
+    let impl_required = match impl_required {
+        true => Some(crate::marker::impl_required()),
+        false => None
+    };
+
+    return quote_spanned! { span =>
+        #[allow(deprecated)]
+        #unsafety impl #impl_generics #trait_ident #ty_generics for #this #where_clause {
+            #(#items)*
+            #impl_required
+        }
+
+        #(#errors)*
+    }
+}
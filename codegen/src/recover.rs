@@ -0,0 +1,49 @@
+use proc_macro2::{TokenTree, Delimiter};
+use syn::{*, parse::{Parse, ParseStream, discouraged::Speculative}};
+
+/// Parses items until the end of the stream. An item that fails to parse is skipped up to the end of its declaration
+/// and replaced by `recover(error)`, so that a single faulty item doesn't take the rest of them down with it.
+pub fn parse_items<T: Parse> (input: ParseStream, recover: impl Fn(Error) -> T) -> Vec<T> {
+    let mut items = Vec::new();
+
+    while !input.is_empty() {
+        let fork = input.fork();
+        match fork.parse::<T>() {
+            Ok(item) => {
+                input.advance_to(&fork);
+                items.push(item);
+            },
+
+            Err(e) => {
+                skip_item(input);
+                items.push(recover(e));
+            }
+        }
+    }
+
+    return items
+}
+
+/// Skips tokens until the end of the current item: a `;` or a braced body (along with a trailing `;`, if any)
+fn skip_item (input: ParseStream) {
+    let _ = input.step(|cursor| {
+        let mut rest = *cursor;
+        while let Some((tt, next)) = rest.token_tree() {
+            rest = next;
+            match tt {
+                TokenTree::Punct(punct) if punct.as_char() == ';' => break,
+                TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => {
+                    if let Some((TokenTree::Punct(punct), next)) = rest.token_tree() {
+                        if punct.as_char() == ';' {
+                            rest = next;
+                        }
+                    }
+                    break
+                },
+                _ => {}
+            }
+        }
+
+        return Ok(((), rest))
+    });
+}
@@ -0,0 +1,119 @@
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned, format_ident, ToTokens};
+use syn::*;
+use crate::*;
+
+/// Longest description of the implementation in the messages, so that they fit in the buffer of `__check_future_size`
+const MAX_IMPL_NAME: usize = 200;
+
+/// Emits a const assertion for every async method whose future must not exceed a size (`max_future_size`, or the method's `#[max_size(N)]`),
+/// removing the `#[max_size]` attributes. Only the futures of non-generic methods of non-generic trait implementations can be checked:
+/// `max_future_size` fails on any other implementation, and on implementations with generic methods.
+pub fn check_future_sizes (name: &str, generics: &Generics, trait_path: Option<&Path>, self_ty: &Type, items: &mut [ImplItem], mut max_future_size: Option<usize>) -> TokenStream {
+    let mut name = name.to_string();
+    if name.len() > MAX_IMPL_NAME {
+        let mut end = MAX_IMPL_NAME;
+        while !name.is_char_boundary(end) {
+            end -= 1;
+        }
+        name.truncate(end);
+        name.push_str("...");
+    }
+
+    let mut result = TokenStream::new();
+    if max_future_size.is_some() {
+        let error = match trait_path {
+            None => Some(Error::new_spanned(self_ty, "`max_future_size` can only be used on trait implementations")),
+            Some(_) if !generics.params.is_empty() => Some(Error::new_spanned(&generics.params, "`max_future_size` can't be used on generic implementations, since their futures can't be measured")),
+            Some(_) => None
+        };
+        if let Some(error) = error {
+            result.extend(error.into_compile_error());
+            max_future_size = None;
+        }
+    }
+
+    for item in items.iter_mut() {
+        let method = match item {
+            ImplItem::Method(method) if method.sig.asyncness.is_some() || returns_future(&method.sig.output) => method,
+            ImplItem::Method(method) => {
+                if let Some(attr) = take_max_size(&mut method.attrs) {
+                    result.extend(Error::new_spanned(attr, "only the futures of async methods can have a size limit").into_compile_error());
+                }
+                continue
+            },
+            _ => continue
+        };
+
+        let (limit, explicit) = match take_max_size(&mut method.attrs) {
+            Some(attr) => match attr.parse_args::<LitInt>().and_then(|x| x.base10_parse::<usize>()) {
+                Ok(limit) => (limit, Some(attr)),
+                Err(e) => {
+                    result.extend(e.into_compile_error());
+                    continue
+                }
+            },
+            None => match max_future_size {
+                Some(limit) => (limit, None),
+                None => continue
+            }
+        };
+
+        let ident = &method.sig.ident;
+        let error = |message: String| match &explicit {
+            Some(attr) => Error::new_spanned(attr, message).into_compile_error(),
+            None => TokenStream::new()
+        };
+
+        let trait_path = match trait_path {
+            Some(path) => path,
+            None => {
+                result.extend(error(format!("the future of method `{ident}` can't have a size limit, since it isn't part of a trait implementation")));
+                continue
+            }
+        };
+        if !generics.params.is_empty() {
+            result.extend(error(format!("the future of method `{ident}` can't have a size limit, since the implementation is generic")));
+            continue
+        }
+        if is_boxed(&method.attrs) {
+            result.extend(error(format!("the future of method `{ident}` can't have a size limit, since it's boxed")));
+            continue
+        }
+        if method.sig.generics.type_params().next().is_some() || method.sig.generics.const_params().next().is_some() {
+            let message = format!("the future of method `{ident}` can't have a size limit, since the method is generic");
+            result.extend(match &explicit {
+                Some(_) => error(message),
+                None => Error::new_spanned(&method.sig.generics.params, format!("{message}, so `max_future_size` can't be used (the other methods can have theirs set by `#[max_size]`)")).into_compile_error()
+            });
+            continue
+        }
+
+        // the size doesn't depend on the lifetimes the future borrows
+        let mut sig = method.sig.clone();
+        future_generics(&mut sig.inputs, &mut sig.generics, &used_idents(method.to_token_stream()));
+        let lifetimes = sig.generics.lifetimes().map(|_| quote! { 'static });
+
+        let span = ident.span();
+        // the assertion only exists along with the method, but lints and docs don't apply to it
+        let attrs = method.attrs.iter().filter(|x| x.path.is_ident("cfg"));
+        let future_name = format_ident!("{}", to_pascal_case(&ident.to_string()), span = span);
+        let prefix = format!("the future of method `{ident}` of `{name}` is ");
+        result.extend(quote_spanned! { span =>
+            #(#attrs)*
+            const _: () = ::fast_async_trait::__check_future_size(
+                ::core::mem::size_of::<<#self_ty as #trait_path>::#future_name<#(#lifetimes),*>>(),
+                #limit,
+                #prefix
+            );
+        });
+    }
+
+    return result
+}
+
+/// Removes the `#[max_size(N)]` attribute of a method, returning it
+fn take_max_size (attrs: &mut Vec<Attribute>) -> Option<Attribute> {
+    let idx = attrs.iter().position(|x| x.path.is_ident("max_size"))?;
+    return Some(attrs.remove(idx))
+}
@@ -0,0 +1,120 @@
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned, format_ident, ToTokens};
+use syn::{*, spanned::Spanned};
+use crate::*;
+
+/// Emits `TraitSpawnExt`, an extension trait with a `spawn_<method>` helper for every async method with a `'static` future (all of them if `static_futures` is set),
+/// which runs its future on a `Spawner`. Returns `None` if there are no such methods.
+pub fn define_spawn_ext (attrs: &[Attribute], vis: &Visibility, trait_ident: &Ident, generics: &Generics, items: &[AsyncTraitItem], static_futures: bool) -> Option<TokenStream> {
+    let (_, trait_ty_generics, _) = generics.split_for_impl();
+    let trait_ty_generics = trait_ty_generics.to_token_stream();
+
+    let methods = items.iter()
+        .filter_map(|x| match x {
+            AsyncTraitItem::Method(method) if method.sig.asyncness.is_some() || returns_future(&method.sig.output) => Some(method),
+            _ => None
+        })
+        .filter(|x| static_futures || x.attrs.iter().any(|attr| attr.path.is_ident("detached")))
+        .filter_map(|x| spawn_method(trait_ident, &trait_ty_generics, x))
+        .collect::<Vec<_>>();
+
+    if methods.is_empty() {
+        return None
+    }
+
+    let span = trait_ident.span();
+    let ext_ident = format_ident!("{trait_ident}SpawnExt", span = span);
+    let mut used = used_idents(quote! { #generics #(#items)* });
+    used.insert(trait_ident.to_string());
+    let this = format_ident!("{}", unique_name("This", &used), span = span);
+
+    let trait_attrs = mirrored_attrs(attrs, true).into_iter().filter(|x| !x.path.is_ident("doc"));
+    let doc = format!(" Extension trait of [`{trait_ident}`], spawning the `'static` futures of its methods onto an executor. It's implemented for every implementor of [`{trait_ident}`].");
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let mut blanket_generics = generics.clone();
+    blanket_generics.params.push(parse_quote_spanned! { span => #this: ?::core::marker::Sized + #trait_ident #ty_generics });
+    let (blanket_impl_generics, _, blanket_where) = blanket_generics.split_for_impl();
+
+    return Some(quote_spanned! { span =>
+        #(#trait_attrs)*
+        #[doc = #doc]
+        #vis trait #ext_ident #impl_generics: #trait_ident #ty_generics #where_clause {
+            #(#methods)*
+        }
+
+        impl #blanket_impl_generics #ext_ident #ty_generics for #this #blanket_where {}
+    })
+}
+
+/// Helper spawning the future of a single method, or `None` if its future borrows something (which is reported by the trait itself)
+fn spawn_method (trait_ident: &Ident, trait_ty_generics: &TokenStream, method: &AsyncTraitItemMethod) -> Option<TokenStream> {
+    let span = method.sig.span();
+    let used = used_idents(quote! { #method #trait_ty_generics });
+    let AsyncTraitItemMethod { attrs, sig, default, .. } = method;
+    let Signature { unsafety, ident, mut generics, mut inputs, .. } = sig.clone();
+
+    let mut lifetimes = generics.clone();
+    future_generics(&mut inputs.clone(), &mut lifetimes, &used);
+    if lifetimes.lifetimes().next().is_some() {
+        return None
+    }
+
+    let future_name = format_ident!("{}", to_pascal_case(&ident.to_string()), span = ident.span());
+    let future = match default {
+        _ if is_boxed(attrs) => {
+            let bounds = future_bounds(&sig.asyncness, sig.output.clone(), span);
+            trait_future(&trait_ident.clone().into(), &ident, None, future_output(&bounds)?)
+        },
+        Some(_) => {
+            let alias = format_ident!("{trait_ident}{future_name}Default", span = ident.span());
+            let mut alias_generics = generics.clone();
+            alias_generics.params.insert(0, GenericParam::Type(TypeParam::from(format_ident!("Self", span = span))));
+            let (_, alias_ty_generics, _) = alias_generics.split_for_impl();
+            quote_spanned! { span => #alias #alias_ty_generics }
+        },
+        None => {
+            let (_, ty_generics, _) = generics.split_for_impl();
+            quote_spanned! { span => <Self as #trait_ident #trait_ty_generics>::#future_name #ty_generics }
+        }
+    };
+
+    let args = normalize_args(inputs.iter_mut());
+    let turbofish = turbofish(&generics);
+    let receiver = receiver_kind(&inputs);
+    let self_arg = match receiver {
+        "none" => None,
+        _ => Some(quote_spanned! { span => self, })
+    };
+
+    let spawner_ty = format_ident!("{}", unique_name("S", &used), span = span);
+    let spawner = format_ident!("{}", unique_name("spawner", &used), span = span);
+    let spawner_bound = quote_spanned! { span => ::fast_async_trait::Spawner<#future> };
+    inputs.insert(self_arg.is_some() as usize, parse_quote_spanned! { span => #spawner: #spawner_ty });
+
+    require_static(&ident, &mut generics).ok()?;
+    if receiver == "by_value" {
+        generics.make_where_clause().predicates.push(parse_quote_spanned! { span => Self: ::core::marker::Sized });
+    }
+    generics.params.push(parse_quote_spanned! { span => #spawner_ty: #spawner_bound });
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    let call = quote_spanned! { span => <Self as #trait_ident #trait_ty_generics>::#ident #turbofish (#self_arg #(#args),*) };
+    let call = match unsafety {
+        Some(_) => quote_spanned! { span => unsafe { #call } },
+        None => call
+    };
+
+    let attrs = mirrored_attrs(attrs, false).into_iter().filter(|x| !x.path.is_ident("doc"));
+    let helper = format_ident!("spawn_{ident}", span = ident.span());
+    let doc = format!(" Spawns the future of [`{trait_ident}::{ident}`] onto `{spawner}`, returning its handle");
+
+    return Some(quote_spanned! { span =>
+        #(#attrs)*
+        #[doc = #doc]
+        #[inline]
+        #unsafety fn #helper #impl_generics (#inputs) -> <#spawner_ty as #spawner_bound>::Handle #where_clause {
+            return <#spawner_ty as #spawner_bound>::spawn(#spawner, #call)
+        }
+    })
+}
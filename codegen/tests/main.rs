@@ -0,0 +1,375 @@
+use fast_async_trait_codegen::*;
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{*, spanned::Spanned};
+
+/// Items of an expansion
+fn expanded_items (expanded: TokenStream) -> Vec<Item> {
+    return parse2::<File>(expanded).unwrap().items
+}
+
+/// Trait named `name` among the items of an expansion
+fn find_trait<'a> (items: &'a [Item], name: &str) -> &'a ItemTrait {
+    return items.iter().find_map(|x| match x {
+        Item::Trait(x) if x.ident == name => Some(x),
+        _ => None
+    }).unwrap()
+}
+
+/// Implementation among the items of an expansion whose type is `name` (i.e. `Compat` for `::fast_async_trait::Compat<T>`)
+fn find_impl<'a> (items: &'a [Item], name: &str) -> &'a ItemImpl {
+    return items.iter().find_map(|x| match x {
+        Item::Impl(x) if last_ident(&x.self_ty).is_some_and(|x| x == name) => Some(x),
+        _ => None
+    }).unwrap()
+}
+
+/// Number of implementations among the items of an expansion whose type is `name` (or a reference, if it's `&`)
+fn count_impls (items: &[Item], name: &str) -> usize {
+    return items.iter().filter(|x| match x {
+        Item::Impl(ItemImpl { self_ty, .. }) if name == "&" => matches!(**self_ty, Type::Reference(_)),
+        Item::Impl(ItemImpl { self_ty, .. }) => last_ident(self_ty).is_some_and(|x| x == name),
+        _ => false
+    }).count()
+}
+
+/// Last identifier of the trait an implementation is of
+fn impl_trait (item: &ItemImpl) -> &Ident {
+    return &item.trait_.as_ref().unwrap().1.segments.last().unwrap().ident
+}
+
+/// Last identifier of a path type, i.e. `Compat` for `::fast_async_trait::Compat<T>`
+fn last_ident (ty: &Type) -> Option<&Ident> {
+    return match ty {
+        Type::Path(TypePath { path, .. }) => path.segments.last().map(|x| &x.ident),
+        _ => None
+    }
+}
+
+#[test]
+fn trait_def () {
+    let expanded = async_trait_def(quote!(), quote! {
+        pub trait Test {
+            async fn test (&self) -> u8;
+        }
+    });
+
+    let file = parse2::<File>(expanded).unwrap();
+    let trait_ = file.items.iter().find_map(|x| match x {
+        Item::Trait(x) => Some(x),
+        _ => None
+    }).unwrap();
+
+    assert!(trait_.items.iter().any(|x| matches!(x, TraitItem::Type(TraitItemType { ident, .. }) if ident == "Test")));
+}
+
+#[test]
+fn recovers_items () {
+    let expanded = async_trait_impl(quote!(), quote! {
+        impl Test for u8 {
+            async fn bad (&self) -> { 1 }
+            async fn good (&self) -> u8 { *self }
+        }
+    }).to_string();
+
+    assert!(expanded.contains("compile_error"));
+    assert!(expanded.contains("type Good"));
+}
+
+#[test]
+fn negative_impls () {
+    let imp = parse2::<AsyncItemImpl>(quote!(impl !Send for Test {})).unwrap();
+    assert!(matches!(imp.trait_, Some((Some(_), _, _))));
+
+    let expanded = async_trait_impl(quote!(), quote!(impl !Test {})).to_string();
+    assert!(expanded.contains("inherent implementations can't be negative"));
+}
+
+#[test]
+fn fresh_lifetimes () {
+    let method: ImplItemMethod = parse_quote! {
+        async fn test<'__self__> (&self, x: &'__self__ u8) {}
+    };
+
+    let ImplItemMethod { sig: Signature { mut inputs, mut generics, .. }, .. } = method.clone();
+    let used = used_idents(method.to_token_stream());
+    let (_, receiver, output) = future_generics(&mut inputs, &mut generics, &used);
+
+    assert_eq!(receiver.unwrap().ident, "__self1__");
+    assert_eq!(output.unwrap().ident, "__self1__");
+    assert_eq!(generics.lifetimes().count(), 2);
+}
+
+#[test]
+fn spans () {
+    let def = "pub trait Test {\n    async fn test (&self) -> u8;\n}";
+    let items = expanded_items(async_trait_def(quote!(), def.parse().unwrap()));
+    for item in &find_trait(&items, "Test").items {
+        match item {
+            TraitItem::Type(x) => assert_eq!(x.span().start().line, 2),
+            TraitItem::Method(x) if x.sig.ident == "test" => assert_eq!(x.sig.output.span().start().line, 2),
+            _ => {}
+        }
+    }
+
+    let imp = "impl Test for u8 {\n    async fn test (&self) -> u8 {\n        return *self\n    }\n}";
+    let items = expanded_items(async_trait_impl(quote!(unchecked), imp.parse().unwrap()));
+    for item in &find_impl(&items, "u8").items {
+        match item {
+            ImplItem::Type(x) => assert_eq!(x.span().start().line, 2),
+            ImplItem::Method(x) => assert_eq!(x.sig.output.span().start().line, 2),
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn build_source () {
+    let expanded = build::expand_source(r#"
+        use fast_async_trait::*;
+
+        mod inner {
+            #[fast_async_trait::async_trait_def]
+            pub trait Test {
+                async fn test (&self) -> u8;
+            }
+        }
+
+        #[async_trait_impl]
+        impl inner::Test for u8 {
+            async fn test (&self) -> u8 { *self }
+        }
+    "#).unwrap();
+
+    assert!(!expanded.contains("async_trait_def"));
+    assert!(!expanded.contains("async_trait_impl]"));
+    assert!(expanded.contains("type Test<"));
+    assert!(expanded.contains("inner::Test! {"));
+
+    // inner attributes belong to the including module
+    let error = build::expand_source("#![allow(dead_code)]\nstruct Test;").unwrap_err();
+    assert_eq!(error.span().start().line, 1);
+
+    // without hygiene, the bindings of pattern arguments must avoid the other arguments
+    let expanded = build::expand_source(r#"
+        #[async_trait_def]
+        trait Test {
+            async fn test ((a, b): (u8, u8), __arg0: u8) -> u8 { return a + b + __arg0 }
+        }
+    "#).unwrap();
+    assert!(expanded.contains("let (a, b) = __arg01;"));
+}
+
+#[test]
+fn compat () {
+    let items = expanded_items(async_trait_def(quote!(compat), quote! {
+        pub trait Test {
+            async fn test (&self) -> u8;
+        }
+    }));
+
+    let output = find_trait(&items, "DynTest").items.iter().find_map(|x| match x {
+        TraitItem::Method(x) => Some(&x.sig.output),
+        _ => None
+    }).unwrap();
+    assert!(matches!(output, ReturnType::Type(_, ty) if last_ident(ty).is_some_and(|x| x == "BoxFuture")));
+    assert_eq!(impl_trait(find_impl(&items, "Compat")), "Test");
+
+    // plain, `Send` and `Send + Sync` trait objects
+    assert_eq!(impl_trait(find_impl(&items, "Box")), "DynTest");
+    assert_eq!(count_impls(&items, "Box"), 3);
+    assert_eq!(count_impls(&items, "Arc"), 3);
+    assert_eq!(count_impls(&items, "&"), 3);
+
+    // `Arc`s and references can't forward `&mut self`
+    let items = expanded_items(async_trait_def(quote!(compat), quote! {
+        pub trait Test {
+            async fn test (&mut self) -> u8;
+        }
+    }));
+    assert_eq!(count_impls(&items, "Box"), 3);
+    assert_eq!(count_impls(&items, "Arc"), 0);
+    assert_eq!(count_impls(&items, "&"), 0);
+
+    // trait objects can't forward `self`
+    let items = expanded_items(async_trait_def(quote!(compat), quote! {
+        pub trait Test {
+            async fn test (&self) -> u8;
+            async fn into_test (self) -> u8;
+        }
+    }));
+    assert_eq!(count_impls(&items, "Box"), 0);
+}
+
+#[test]
+fn native () {
+    let items = expanded_items(async_trait_def(quote!(native = "NativeTest"), quote! {
+        pub trait Test {
+            async fn test (&self) -> u8;
+        }
+    }));
+
+    let blanket = items.iter().find_map(|x| match x {
+        Item::Impl(x) if impl_trait(x) == "Test" => Some(x),
+        _ => None
+    }).unwrap();
+    assert!(blanket.generics.type_params().any(|x| x.bounds.iter().any(|x| matches!(x, TypeParamBound::Trait(x) if x.path.is_ident("NativeTest")))));
+
+    let items = expanded_items(async_trait_impl(quote!(native), quote! {
+        impl NativeTest for u8 {
+            async fn test (&self) -> u8 {
+                return *self
+            }
+        }
+    }));
+    assert!(matches!(find_impl(&items, "u8").items.as_slice(), [ImplItem::Method(ImplItemMethod { sig, .. })] if sig.asyncness.is_some()));
+
+    // attributes of associated futures are reported and removed
+    let items = expanded_items(async_trait_impl(quote!(native), quote! {
+        impl NativeTest for u8 {
+            #[boxed]
+            #[max_size(64)]
+            async fn test (&self) -> u8 {
+                return *self
+            }
+        }
+    }));
+    let errors = find_impl(&items, "u8").items.iter()
+        .filter_map(|x| match x {
+            ImplItem::Macro(x) if x.mac.path.segments.last().unwrap().ident == "compile_error" => Some(x.mac.parse_body::<LitStr>().unwrap().value()),
+            _ => None
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(errors, [
+        "`#[boxed]` can't be used in native implementations, since their async methods don't have associated futures",
+        "`#[max_size]` can't be used in native implementations, since their async methods don't have associated futures"
+    ]);
+    assert!(matches!(find_impl(&items, "u8").items.first(), Some(ImplItem::Method(ImplItemMethod { attrs, .. })) if attrs.is_empty()));
+}
+
+#[test]
+fn static_futures () {
+    let items = expanded_items(async_trait_def(quote!(static_futures), quote! {
+        pub trait Test {
+            async fn test (self: Arc<Self>) -> u8;
+        }
+    }));
+
+    // the future is bound by `'static` instead of the receiver's lifetime
+    let future = find_trait(&items, "Test").items.iter().find_map(|x| match x {
+        TraitItem::Type(x) if x.ident == "Test" => Some(x),
+        _ => None
+    }).unwrap();
+    assert!(future.generics.params.is_empty());
+    assert!(future.bounds.iter().any(|x| matches!(x, TypeParamBound::Lifetime(x) if x.ident == "static")));
+
+    let spawn = find_trait(&items, "TestSpawnExt").items.iter().find_map(|x| match x {
+        TraitItem::Method(x) if x.sig.ident == "spawn_test" => Some(x),
+        _ => None
+    }).unwrap();
+    assert!(spawn.default.is_some());
+    assert_eq!(spawn.sig.inputs.len(), 2);
+}
+
+#[test]
+fn boxed () {
+    let items = expanded_items(async_trait_def(quote!(), quote! {
+        pub trait Test {
+            #[boxed]
+            async fn test (&self) -> u8;
+        }
+    }));
+
+    let test_trait = find_trait(&items, "Test");
+    assert!(!test_trait.items.iter().any(|x| matches!(x, TraitItem::Type(_))));
+
+    // `Pin<Box<dyn Future<Output = u8> + '_>>`
+    let output = test_trait.items.iter().find_map(|x| match x {
+        TraitItem::Method(x) if x.sig.ident == "test" => Some(&x.sig.output),
+        _ => None
+    }).unwrap();
+    let pointer = match output {
+        ReturnType::Type(_, ty) => match &**ty {
+            Type::Path(TypePath { path, .. }) if path.segments.last().unwrap().ident == "Pin" => match &path.segments.last().unwrap().arguments {
+                PathArguments::AngleBracketed(args) => args.args.first().cloned(),
+                _ => None
+            },
+            _ => None
+        },
+        ReturnType::Default => None
+    };
+    assert!(matches!(pointer, Some(GenericArgument::Type(ty)) if last_ident(&ty).is_some_and(|x| x == "Box")));
+}
+
+#[test]
+fn conflicting_options () {
+    let def_error = parse2::<DefArgs>(quote!(native = "NativeTest", compat)).err().unwrap();
+    assert_eq!(def_error.to_string(), "option `compat` can't be used together with `native`, since the futures of the native trait can't be required to be `Send`");
+
+    let impl_error = parse2::<ImplArgs>(quote!(native, unchecked)).err().unwrap();
+    assert_eq!(impl_error.to_string(), "option `unchecked` can't be used together with `native`, since native implementations are never checked");
+
+    let impl_error = parse2::<ImplArgs>(quote!(max_future_size = 64, native)).err().unwrap();
+    assert_eq!(impl_error.to_string(), "option `native` can't be used together with `max_future_size`, since the futures of native implementations can't be named");
+}
+
+#[test]
+fn future_sizes () {
+    // the assertions are compiled out with their methods, but don't take their other attributes
+    let items = expanded_items(async_trait_impl(quote!(max_future_size = 64), quote! {
+        impl Test for u8 {
+            /// Test
+            #[cfg(feature = "test")]
+            #[allow(unused)]
+            async fn test (&self) -> u8 {
+                return *self
+            }
+        }
+    }));
+    let assertion = items.iter().find_map(|x| match x {
+        Item::Const(x) => Some(x),
+        _ => None
+    }).unwrap();
+    assert!(assertion.attrs.iter().map(|x| x.path.get_ident().unwrap().to_string()).eq(["cfg"]));
+
+    // generic and inherent implementations, and implementations with generic methods, can't be checked at all
+    for (item, message) in [
+        (quote!(impl<T> Test for Vec<T> { async fn test (&self) -> u8 { return 1 } }), "`max_future_size` can't be used on generic implementations, since their futures can't be measured"),
+        (quote!(impl Inherent { async fn test (&self) -> u8 { return 1 } }), "`max_future_size` can only be used on trait implementations"),
+        (
+            quote!(impl Test for u8 { async fn test<T: Into<u8>> (&self, value: T) -> u8 { return value.into() } }),
+            "the future of method `test` can't have a size limit, since the method is generic, so `max_future_size` can't be used (the other methods can have theirs set by `#[max_size]`)"
+        )
+    ] {
+        let items = expanded_items(async_trait_impl(quote!(max_future_size = 64), item));
+        let errors = items.iter()
+            .filter_map(|x| match x {
+                Item::Macro(x) if x.mac.path.segments.last().unwrap().ident == "compile_error" => Some(x.mac.parse_body::<LitStr>().unwrap().value()),
+                _ => None
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(errors, [message]);
+    }
+}
+
+#[test]
+fn debug_dumps () {
+    let out_dir = std::env::temp_dir().join(format!("fast_async_trait_dumps_{}", std::process::id()));
+    std::env::set_var("OUT_DIR", &out_dir);
+
+    // traits with the same name in different modules
+    for value in [1u8, 2] {
+        async_trait_def(quote!(debug), quote! {
+            trait Dumped {
+                async fn test (&self) -> u8 { return #value }
+            }
+        });
+    }
+
+    let dumps = out_dir.join("fast_async_trait");
+    let first = std::fs::read_to_string(dumps.join("Dumped.rs")).unwrap();
+    let second = std::fs::read_to_string(dumps.join("Dumped_2.rs")).unwrap();
+    assert!(first.contains("return 1u8"));
+    assert!(second.contains("return 2u8"));
+    std::fs::remove_dir_all(out_dir).unwrap();
+}
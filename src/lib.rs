@@ -1,11 +1,151 @@
-#![feature(unboxed_closures, fn_traits)]
+#![no_std]
+#![cfg_attr(feature = "fn_traits", feature(unboxed_closures, tuple_trait))]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "macros")]
 extern crate fast_async_trait_proc;
+#[cfg(feature = "macros")]
 pub use fast_async_trait_proc::*;
 
+/// Future returned by the methods of the object safe counterparts of `#[async_trait_def(compat)]` traits
+#[cfg(feature = "alloc")]
+pub type BoxFuture<'a, T> = core::pin::Pin<alloc::boxed::Box<dyn core::future::Future<Output = T> + Send + 'a>>;
+
+/// Future returned by the methods of the object safe counterparts of `#[async_trait_def(compat(local))]` traits
+#[cfg(feature = "alloc")]
+pub type LocalBoxFuture<'a, T> = core::pin::Pin<alloc::boxed::Box<dyn core::future::Future<Output = T> + 'a>>;
+
+/// Wrapper implementing an `#[async_trait_def(compat)]` trait for the implementors of its object safe counterpart
+/// (including `Box`es, `Arc`s and references of trait objects, i.e. `Compat<Box<dyn DynTrait>>` can be used as a `Trait`)
+#[repr(transparent)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Compat<T: ?Sized>(pub T);
+
+/// Executor running `'static` futures in the background, taken by the `spawn_*` helpers generated for the methods with a `'static` future.
+/// It's implemented for closures and functions spawning the future, i.e. `tokio::spawn`.
+pub trait Spawner<F: core::future::Future> {
+    /// Handle to the spawned task
+    type Handle;
+
+    /// Spawns `future` onto the executor
+    fn spawn (self, future: F) -> Self::Handle;
+}
+
+impl<F: core::future::Future, H, T: FnOnce(F) -> H> Spawner<F> for T {
+    type Handle = H;
+
+    #[inline]
+    fn spawn (self, future: F) -> H {
+        return self(future)
+    }
+}
+
+#[doc(hidden)]
+#[cfg(feature = "alloc")]
+pub mod __private {
+    pub use alloc::boxed::Box;
+    #[cfg(target_has_atomic = "ptr")]
+    pub use alloc::sync::Arc;
+}
+
+/// Annotates the default implementation of an async method with the type aliases it defines, on compilers that require it
+#[doc(hidden)]
+#[cfg(fast_async_trait_define_opaque)]
+#[macro_export]
+macro_rules! __define_opaque {
+    ([$($alias:path),*] $item:item) => {
+        #[define_opaque($($alias),*)]
+        $item
+    };
+}
+
+#[doc(hidden)]
+#[cfg(not(fast_async_trait_define_opaque))]
+#[macro_export]
+macro_rules! __define_opaque {
+    ([$($alias:path),*] $item:item) => {
+        $item
+    };
+}
+
+/// Fails const evaluation if the future of a method is larger than `max` bytes, with a message starting with `prefix` (which must fit in the buffer) and followed by its size
+#[doc(hidden)]
+pub const fn __check_future_size (size: usize, max: usize, prefix: &str) {
+    if size <= max {
+        return
+    }
+
+    let mut buf = [0u8; 512];
+    let len = write_str(&mut buf, 0, prefix);
+    let len = write_usize(&mut buf, len, size);
+    let len = write_str(&mut buf, len, " bytes, exceeding the limit of ");
+    let len = write_usize(&mut buf, len, max);
+    let len = write_str(&mut buf, len, " bytes");
+
+    let (msg, _) = buf.split_at(len);
+    match core::str::from_utf8(msg) {
+        Ok(msg) => panic!("{}", msg),
+        Err(_) => panic!("future exceeds its size limit")
+    }
+}
+
+const fn write_str (buf: &mut [u8], mut len: usize, s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() && len < buf.len() {
+        buf[len] = bytes[i];
+        len += 1;
+        i += 1;
+    }
+    return len
+}
+
+const fn write_usize (buf: &mut [u8], len: usize, mut n: usize) -> usize {
+    let mut digits = [0u8; 20];
+    let mut count = 0;
+    loop {
+        digits[count] = b'0' + (n % 10) as u8;
+        count += 1;
+        n /= 10;
+        if n == 0 {
+            break
+        }
+    }
+
+    let mut len = len;
+    while count > 0 && len < buf.len() {
+        count -= 1;
+        buf[len] = digits[count];
+        len += 1;
+    }
+    return len
+}
+
+/// Fails on compilers that don't support the features the expansion relies on
+#[doc(hidden)]
+#[cfg(fast_async_trait_nightly)]
+#[macro_export]
+macro_rules! __check_features {
+    () => {};
+}
+
+#[doc(hidden)]
+#[cfg(not(fast_async_trait_nightly))]
+#[macro_export]
+macro_rules! __check_features {
+    () => {
+        ::core::compile_error! { "`fast_async_trait` requires a nightly compiler, with `#![feature(type_alias_impl_trait, impl_trait_in_assoc_type)]` enabled" }
+    };
+}
+
 #[doc(hidden)]
+#[cfg(feature = "fn_traits")]
 pub trait FnOnceHelper {
-    type Args;
+    type Args: core::marker::Tuple;
     type Output;
 
     extern "rust-call" fn call_once(self, args: Self::Args) -> Self::Output;
@@ -1,4 +1,4 @@
-#![feature(type_alias_impl_trait)]
+#![feature(type_alias_impl_trait, impl_trait_in_assoc_type, associated_type_defaults)]
 use fast_async_trait::*;
 
 #[async_trait_def]
@@ -57,6 +57,600 @@ impl AsyncTrait for (usize, &[u8]) {
     }
 }
 
+#[async_trait_def]
+pub trait PatternTrait {
+    async fn sum (&self, (a, b): (u32, u32), mut buf: Vec<u32>) -> u32;
+
+    #[inline]
+    async fn sum_default (self, [a, b]: [u32; 2]) -> u32 where Self: Sized {
+        return a + b
+    }
+}
+
+#[async_trait_impl]
+impl PatternTrait for u32 {
+    #[inline]
+    async fn sum (&self, (a, b): (u32, u32), mut buf: Vec<u32>) -> u32 {
+        buf.push(*self + a + b);
+        return buf.into_iter().sum()
+    }
+}
+
+#[async_trait_def]
+pub trait BorrowedTrait {
+    async fn get (&self, key: usize) -> Option<&u8>;
+    async fn first (values: &[u8]) -> Option<&u8>;
+}
+
+#[async_trait_impl]
+impl BorrowedTrait for Vec<u8> {
+    #[inline]
+    async fn get (&self, key: usize) -> Option<&u8> {
+        return self.as_slice().get(key)
+    }
+
+    #[inline]
+    async fn first (values: &[u8]) -> Option<&u8> {
+        return values.first()
+    }
+}
+
+#[async_trait_def]
+pub trait OpaqueOutputTrait {
+    async fn values (&self) -> impl Iterator<Item = &u8> + '_;
+    async fn pair (self) -> (impl core::fmt::Debug, Option<impl Iterator<Item = impl core::fmt::Debug>>);
+    async fn dynamic (&mut self) -> Box<dyn core::fmt::Debug + '_>;
+}
+
+#[async_trait_impl]
+impl OpaqueOutputTrait for Vec<u8> {
+    #[inline]
+    async fn values (&self) -> impl Iterator<Item = &u8> + '_ {
+        return self.as_slice().iter()
+    }
+
+    #[inline]
+    async fn pair (self) -> (impl core::fmt::Debug, Option<impl Iterator<Item = impl core::fmt::Debug>>) {
+        return (self.len(), Some(self.into_iter()))
+    }
+
+    #[inline]
+    async fn dynamic (&mut self) -> Box<dyn core::fmt::Debug + '_> {
+        if self.is_empty() {
+            return Box::new(0u8)
+        }
+        return Box::new(&*self)
+    }
+}
+
+#[async_trait_def]
+pub trait ExplicitFutureTrait {
+    fn fetch (&self, n: usize) -> impl core::future::Future<Output = Vec<u8>> + Send;
+    fn peek (&self) -> impl core::future::Future<Output = Option<&u8>> + Send + '_;
+}
+
+#[async_trait_impl]
+impl ExplicitFutureTrait for Vec<u8> {
+    #[inline]
+    fn fetch (&self, n: usize) -> impl core::future::Future<Output = Vec<u8>> + Send {
+        let n = n.min(self.len());
+        return async move { self[..n].to_vec() }
+    }
+
+    #[inline]
+    fn peek (&self) -> impl core::future::Future<Output = Option<&u8>> + Send + '_ {
+        return core::future::ready(self.first())
+    }
+}
+
+#[async_trait_def]
+pub trait DefaultTypeTrait {
+    type Item: Default = u8;
+
+    async fn get (&self) -> <Self as DefaultTypeTrait>::Item;
+
+    #[inline]
+    async fn get_or_default (&self, present: bool) -> Option<Self::Item> {
+        if present {
+            return Some(self.get().await)
+        }
+        return Some(Default::default())
+    }
+}
+
+#[async_trait_impl]
+impl DefaultTypeTrait for u16 {
+    #[inline]
+    async fn get (&self) -> u8 {
+        return *self as u8
+    }
+}
+
+#[async_trait_def]
+pub trait GenericDefaultTrait<K: 'static> where K: Copy {
+    type Item;
+
+    async fn get (&self, key: K) -> Option<Self::Item>;
+
+    #[inline]
+    async fn get_all (&self, keys: Vec<K>) -> Vec<Self::Item> {
+        let mut result = Vec::new();
+        for key in keys {
+            result.extend(self.get(key).await);
+        }
+        return result
+    }
+}
+
+#[async_trait_impl]
+impl GenericDefaultTrait<usize> for Vec<u8> {
+    type Item = u8;
+
+    #[inline]
+    async fn get (&self, key: usize) -> Option<u8> {
+        return self.as_slice().get(key).copied()
+    }
+}
+
+#[test]
+fn generic_default () {
+    let values = vec![1, 2, 3];
+    assert_eq!(futures::executor::block_on(GenericDefaultTrait::get_all(&values, vec![0, 2, 5])), [1, 3]);
+}
+
+#[async_trait_def]
+pub trait CollidingTrait {
+    async fn this<This: Copy + 'static> (&self, value: This) -> This {
+        return value
+    }
+
+    async fn lifetime (&self, __self__: u8, f: for<'__self__> fn(&'__self__ u8) -> u8) -> u8;
+}
+
+#[async_trait_impl]
+impl CollidingTrait for u8 {
+    #[inline]
+    async fn lifetime (&self, __self__: u8, f: for<'__self__> fn(&'__self__ u8) -> u8) -> u8 {
+        return f(self) + __self__
+    }
+}
+
+macro_rules! getters {
+    (trait $($name:ident),+) => {
+        fast_async_trait::async_trait_def_items! {
+            $(async fn $name (&self) -> u8;)+
+        }
+    };
+
+    (impl $($name:ident => $value:expr),+) => {
+        fast_async_trait::async_trait_impl_items! {
+            $(
+                #[inline]
+                async fn $name (&self) -> u8 {
+                    return $value
+                }
+            )+
+        }
+    };
+}
+
+#[async_trait_def]
+pub trait MacroTrait {
+    getters!(trait first, second);
+    async fn third (&self) -> u8;
+}
+
+#[async_trait_impl]
+impl MacroTrait for u8 {
+    getters!(impl first => 1, second => 2);
+
+    #[inline]
+    async fn third (&self) -> u8 {
+        return 3
+    }
+}
+
+pub trait HandWrittenTrait {
+    type Next<'a>: 'a + core::future::Future<Output = Option<u8>> where Self: 'a;
+    fn next<'a> (&'a mut self) -> Self::Next<'a> where Self: 'a;
+}
+
+#[async_trait_impl(unchecked)]
+impl HandWrittenTrait for core::ops::Range<u8> {
+    #[inline]
+    async fn next (&mut self) -> Option<u8> {
+        return Iterator::next(self)
+    }
+}
+
+#[async_trait_def]
+pub trait AttributeTrait {
+    /// Only available with a feature that doesn't exist
+    #[cfg(any())]
+    async fn missing (&self) -> u8;
+
+    #[cfg(any())]
+    async fn missing_default (&self) -> u8 {
+        return 0
+    }
+
+    #[cfg_attr(all(), allow(unused_variables), must_use)]
+    async fn present (&self, unused: u8) -> u8;
+
+    #[deprecated]
+    async fn old (&self) -> u8;
+
+    #[deprecated]
+    async fn old_default (&self) -> u8 {
+        return 0
+    }
+
+    #[expect(unused_variables)]
+    async fn expected_default (&self, unused: u8) -> u8 {
+        return 0
+    }
+
+    async fn expected (&self, unused: u8) -> u8;
+}
+
+#[async_trait_impl]
+impl AttributeTrait for u8 {
+    #[cfg(any())]
+    async fn missing (&self) -> u8 {
+        return *self
+    }
+
+    #[allow(unused_variables)]
+    async fn present (&self, unused: u8) -> u8 {
+        return *self
+    }
+
+    async fn old (&self) -> u8 {
+        return *self
+    }
+
+    #[expect(unused_variables)]
+    async fn expected (&self, unused: u8) -> u8 {
+        return *self
+    }
+}
+
+macro_rules! counter {
+    ($trait:ident, $ty:ty) => {
+        fast_async_trait::async_trait! {
+            pub trait $trait {
+                async fn count (&self, extra: u8) -> u8;
+            }
+        }
+
+        fast_async_trait::async_impl! {
+            impl $crate::$trait for $ty {
+                async fn count (&self, extra: u8) -> u8 {
+                    let value = *self;
+                    return value + extra
+                }
+            }
+        }
+    };
+}
+
+counter!(CounterTrait, u8);
+
+macro_rules! store {
+    ($doc:literal, $ty:ty) => {
+        fast_async_trait::async_trait! {
+            #[doc = $doc]
+            pub trait Store {
+                async fn load (&self) -> u8;
+            }
+        }
+
+        fast_async_trait::async_impl! {
+            impl Store for $ty {
+                #[inline]
+                async fn load (&self) -> u8 {
+                    return *self
+                }
+            }
+        }
+    };
+}
+
+pub mod first_store {
+    store!("First store", u8);
+}
+
+pub mod second_store {
+    store!("Second store", u8);
+}
+
+mod private_store {
+    use fast_async_trait::*;
+
+    #[async_trait_def]
+    pub(crate) trait PrivateStore {
+        async fn load (&self) -> u8;
+    }
+
+    #[async_trait_impl]
+    impl PrivateStore for u8 {
+        #[inline]
+        async fn load (&self) -> u8 {
+            return *self
+        }
+    }
+}
+
+#[inline]
+pub async fn load_private (value: u8) -> u8 {
+    use private_store::PrivateStore;
+    return value.load().await
+}
+
+#[async_trait_def(compat)]
+pub trait CompatTrait: Send + Sync {
+    type Item;
+
+    async fn get (&self, idx: usize) -> Option<Self::Item>;
+    async fn set (&mut self, (idx, value): (usize, Self::Item));
+    fn size (&self) -> usize;
+
+    #[inline]
+    async fn head (&self) -> Option<Self::Item> {
+        return self.get(0).await
+    }
+}
+
+#[async_trait_impl]
+impl CompatTrait for Vec<u8> {
+    type Item = u8;
+
+    #[inline]
+    async fn get (&self, idx: usize) -> Option<u8> {
+        return self.as_slice().get(idx).copied()
+    }
+
+    #[inline]
+    async fn set (&mut self, (idx, value): (usize, u8)) {
+        self[idx] = value
+    }
+
+    #[inline]
+    fn size (&self) -> usize {
+        return Vec::len(self)
+    }
+}
+
+pub struct BoxedImpl(u8);
+
+impl DynCompatTrait for BoxedImpl {
+    type Item = u8;
+
+    fn get<'a> (&'a self, idx: usize) -> fast_async_trait::BoxFuture<'a, Option<u8>> {
+        return Box::pin(async move { (idx == 0).then_some(self.0) })
+    }
+
+    fn set<'a> (&'a mut self, (_, value): (usize, u8)) -> fast_async_trait::BoxFuture<'a, ()> {
+        return Box::pin(async move { self.0 = value })
+    }
+
+    fn head<'a> (&'a self) -> fast_async_trait::BoxFuture<'a, Option<u8>> {
+        return self.get(0)
+    }
+
+    fn size (&self) -> usize {
+        return 1
+    }
+}
+
+pub type CompatObject = fast_async_trait::Compat<dyn DynCompatTrait<Item = u8>>;
+
+#[async_trait_def(compat(local))]
+pub trait SharedCompatTrait {
+    type Item: Clone;
+
+    async fn first (&self) -> Self::Item;
+}
+
+impl DynSharedCompatTrait for BoxedImpl {
+    type Item = u8;
+
+    fn first<'a> (&'a self) -> fast_async_trait::LocalBoxFuture<'a, u8> {
+        return Box::pin(async move { self.0 })
+    }
+}
+
+#[test]
+fn compat_pointers () {
+    futures::executor::block_on(async {
+        let mut boxed = Compat(Box::new(BoxedImpl(5)) as Box<dyn DynCompatTrait<Item = u8>>);
+        assert_eq!(CompatTrait::get(&boxed, 0).await, Some(5));
+        CompatTrait::set(&mut boxed, (0, 7)).await;
+        assert_eq!(CompatTrait::head(&boxed).await, Some(7));
+        assert_eq!(CompatTrait::size(&boxed), 1);
+
+        let shared = std::sync::Arc::new(BoxedImpl(3)) as std::sync::Arc<dyn DynSharedCompatTrait<Item = u8>>;
+        assert_eq!(SharedCompatTrait::first(&Compat(shared.clone())).await, 3);
+        assert_eq!(SharedCompatTrait::first(&Compat(&*shared)).await, 3);
+    });
+}
+
+#[allow(async_fn_in_trait)]
+pub trait NativeTrait {
+    type Item;
+    async fn get (&self, idx: usize) -> Option<Self::Item>;
+    async fn build (len: usize) -> Self;
+}
+
+#[async_trait_def(native = "NativeTrait")]
+pub trait FromNativeTrait {
+    type Item;
+    async fn get (&self, idx: usize) -> Option<Self::Item>;
+    async fn build (len: usize) -> Self;
+
+    #[inline]
+    async fn head (&self) -> Option<Self::Item> {
+        return self.get(0).await
+    }
+}
+
+pub struct NativeImpl(Vec<u16>);
+
+#[async_trait_impl(native)]
+impl NativeTrait for NativeImpl {
+    type Item = u16;
+
+    #[inline]
+    async fn get (&self, idx: usize) -> Option<u16> {
+        return self.0.get(idx).copied()
+    }
+
+    #[inline]
+    async fn build (len: usize) -> Self {
+        return NativeImpl(vec![0; len])
+    }
+}
+
+#[test]
+fn native_blanket () {
+    futures::executor::block_on(async {
+        let native = <NativeImpl as FromNativeTrait>::build(2).await;
+        assert_eq!(FromNativeTrait::get(&native, 1).await, Some(0));
+        assert_eq!(FromNativeTrait::head(&native).await, Some(0));
+        assert_eq!(FromNativeTrait::get(&native, 2).await, None);
+    });
+}
+
+#[async_trait_def]
+pub trait DetachedTrait {
+    #[detached]
+    async fn fetch (self: std::sync::Arc<Self>, idx: usize) -> Option<u8>;
+    #[detached]
+    async fn convert<T: Into<u64>> (value: T) -> u64;
+    async fn peek (&self) -> u8;
+}
+
+#[async_trait_impl]
+impl DetachedTrait for Vec<u8> {
+    #[inline]
+    async fn fetch (self: std::sync::Arc<Self>, idx: usize) -> Option<u8> {
+        return self.as_slice().get(idx).copied()
+    }
+
+    #[inline]
+    #[detached]
+    async fn convert<T: Into<u64>> (value: T) -> u64 {
+        return value.into()
+    }
+
+    #[inline]
+    async fn peek (&self) -> u8 {
+        return self[0]
+    }
+}
+
+#[async_trait_def(static_futures)]
+pub trait StaticTrait<T> {
+    async fn consume (self, value: T) -> T;
+}
+
+pub fn spawn_fetch (values: std::sync::Arc<Vec<u8>>) -> impl std::future::Future<Output = Option<u8>> {
+    return values.spawn_fetch(|future| future, 0)
+}
+
+#[test]
+fn detached () {
+    let values = std::sync::Arc::new(vec![4, 5]);
+    let mut pool = futures::executor::LocalPool::new();
+    let spawner = pool.spawner();
+
+    // the future outlives the spawning function
+    let handle = values.clone().spawn_fetch(|future| futures::task::LocalSpawnExt::spawn_local_with_handle(&spawner, future).unwrap(), 1);
+    assert_eq!(pool.run_until(handle), Some(5));
+    assert_eq!(pool.run_until(<Vec<u8> as DetachedTrait>::convert(7u8)), 7);
+}
+
+pub struct TreeNode {
+    pub value: u32,
+    pub children: Vec<TreeNode>,
+}
+
+#[async_trait_def]
+pub trait BoxedTrait {
+    #[boxed]
+    async fn total (&self) -> u32;
+    #[inline]
+    #[boxed]
+    async fn count (&self) -> usize {
+        return 1
+    }
+    async fn unboxed (&self) -> u32;
+}
+
+#[async_trait_impl]
+impl BoxedTrait for TreeNode {
+    #[boxed]
+    async fn total (&self) -> u32 {
+        let mut total = self.value;
+        for child in &self.children {
+            total += child.total().await;
+        }
+        return total
+    }
+
+    #[inline]
+    async fn unboxed (&self) -> u32 {
+        return self.value
+    }
+}
+
+#[test]
+fn boxed_recursion () {
+    let tree = TreeNode {
+        value: 1,
+        children: vec![
+            TreeNode { value: 2, children: Vec::new() },
+            TreeNode { value: 3, children: vec![TreeNode { value: 4, children: Vec::new() }] }
+        ]
+    };
+
+    futures::executor::block_on(async {
+        assert_eq!(tree.total().await, 10);
+        assert_eq!(tree.count().await, 1);
+        assert_eq!(tree.unboxed().await, 1);
+    });
+}
+
+#[async_trait_def]
+pub trait SizedTrait {
+    async fn small (&self) -> u8;
+    async fn large (&self) -> u8;
+    #[cfg(any())]
+    async fn disabled (&self) -> u8;
+}
+
+#[async_trait_impl(max_future_size = 64)]
+impl SizedTrait for u8 {
+    #[inline]
+    async fn small (&self) -> u8 {
+        return *self
+    }
+
+    #[inline]
+    #[max_size(2048)]
+    async fn large (&self) -> u8 {
+        let buf = [*self; 1024];
+        core::future::ready(()).await;
+        return buf[0]
+    }
+
+    // its assertion is compiled out with it
+    #[cfg(any())]
+    async fn disabled (&self) -> u8 {
+        return [*self; 1024][0]
+    }
+}
+
 /*#[async_trait_impl]
 impl AsyncIterator for (usize, &[u16]) {
     type Item = u16;
@@ -0,0 +1,7 @@
+//! Implementations the macros must reject, along with the errors they report
+
+#[test]
+fn ui () {
+    let tests = trybuild::TestCases::new();
+    tests.compile_fail("tests/ui/*.rs");
+}
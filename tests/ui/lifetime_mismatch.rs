@@ -0,0 +1,17 @@
+#![feature(type_alias_impl_trait, impl_trait_in_assoc_type)]
+use fast_async_trait::*;
+
+#[async_trait_def]
+pub trait Lookup {
+    async fn first (values: &[u8]) -> Option<&u8>;
+}
+
+#[async_trait_impl]
+impl Lookup for u8 {
+    #[inline]
+    async fn first (values: &'static [u8]) -> Option<&'static u8> {
+        return values.first()
+    }
+}
+
+fn main () {}